@@ -0,0 +1,171 @@
+use std::collections::VecDeque;
+
+/// A multi-pattern matcher that finds every (possibly overlapping) occurrence of a set of
+/// byte patterns in a single pass over the haystack.
+///
+/// The automaton is stored as a complete transition table, so every input byte costs
+/// exactly one lookup. It can also be driven byte by byte through [`AhoCorasick::step`],
+/// which lets callers feed input that arrives in chunks.
+#[derive(Debug, Clone)]
+pub struct AhoCorasick {
+    transitions: Vec<[usize; 256]>,
+    outputs: Vec<Vec<usize>>,
+    lengths: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Match {
+    pub pattern: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl AhoCorasick {
+    pub const START: usize = 0;
+
+    /// Builds the automaton. Patterns are identified by their position; empty patterns never match.
+    pub fn new<P: AsRef<[u8]>>(patterns: impl IntoIterator<Item = P>) -> Self {
+        let mut transitions = vec![[usize::MAX; 256]];
+        let mut outputs = vec![vec![]];
+        let mut lengths = vec![];
+
+        for (index, pattern) in patterns.into_iter().enumerate() {
+            let pattern = pattern.as_ref();
+            lengths.push(pattern.len());
+            if pattern.is_empty() {
+                continue;
+            }
+
+            let mut state = Self::START;
+            for byte in pattern {
+                if transitions[state][*byte as usize] == usize::MAX {
+                    transitions.push([usize::MAX; 256]);
+                    outputs.push(vec![]);
+                    transitions[state][*byte as usize] = transitions.len() - 1;
+                }
+                state = transitions[state][*byte as usize];
+            }
+            outputs[state].push(index);
+        }
+
+        // turn the trie into a complete automaton, breadth first so that the failure
+        // state of every node is finished before the node itself
+        let mut fail = vec![Self::START; transitions.len()];
+        let mut queue = VecDeque::new();
+
+        for next in transitions[Self::START].iter_mut() {
+            match *next {
+                usize::MAX => *next = Self::START,
+                next => queue.push_back(next),
+            }
+        }
+
+        while let Some(state) = queue.pop_front() {
+            let inherited = outputs[fail[state]].clone();
+            outputs[state].extend(inherited);
+
+            let fallbacks = transitions[fail[state]];
+            for (next, fallback) in transitions[state].iter_mut().zip(fallbacks) {
+                match *next {
+                    usize::MAX => *next = fallback,
+                    next => {
+                        fail[next] = fallback;
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+
+        AhoCorasick {
+            transitions,
+            outputs,
+            lengths,
+        }
+    }
+
+    pub fn step(&self, state: usize, byte: u8) -> usize {
+        self.transitions[state][byte as usize]
+    }
+
+    /// The patterns that end in `state`, longest first.
+    pub fn outputs(&self, state: usize) -> &[usize] {
+        &self.outputs[state]
+    }
+
+    pub fn pattern_len(&self, pattern: usize) -> usize {
+        self.lengths[pattern]
+    }
+
+    /// Every occurrence of every pattern, ordered by end position.
+    pub fn find_overlapping<'a>(&'a self, haystack: &'a [u8]) -> impl Iterator<Item = Match> + 'a {
+        haystack
+            .iter()
+            .enumerate()
+            .scan(Self::START, move |state, (i, byte)| {
+                *state = self.step(*state, *byte);
+                Some((i + 1, *state))
+            })
+            .flat_map(move |(end, state)| {
+                self.outputs(state).iter().map(move |pattern| Match {
+                    pattern: *pattern,
+                    start: end - self.lengths[*pattern],
+                    end,
+                })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(patterns: &[&str], haystack: &str) -> Vec<(usize, usize, usize)> {
+        AhoCorasick::new(patterns)
+            .find_overlapping(haystack.as_bytes())
+            .map(|found| (found.pattern, found.start, found.end))
+            .collect()
+    }
+
+    #[test]
+    fn test_overlapping() {
+        assert_eq!(
+            find(&["he", "she", "his", "hers"], "ushers"),
+            vec![(1, 1, 4), (0, 2, 4), (3, 2, 6)]
+        );
+        assert_eq!(
+            find(&["eight", "two", "one"], "eightwone"),
+            vec![(0, 0, 5), (1, 4, 7), (2, 6, 9)]
+        );
+        assert_eq!(find(&["aa"], "aaaa"), vec![(0, 0, 2), (0, 1, 3), (0, 2, 4)]);
+    }
+
+    #[test]
+    fn test_no_matches() {
+        assert_eq!(find(&["abc"], "ababab"), vec![]);
+        assert_eq!(find(&["", "x"], "axb"), vec![(1, 1, 2)]);
+        assert_eq!(find(&[], "anything"), vec![]);
+    }
+
+    #[test]
+    fn test_multibyte_patterns() {
+        assert_eq!(
+            find(&["drei", "für", "ü"], "fürdrei"),
+            vec![(2, 1, 3), (1, 0, 4), (0, 4, 8)]
+        );
+    }
+
+    #[test]
+    fn test_step_across_chunks() {
+        let automaton = AhoCorasick::new(["seven"]);
+
+        let mut state = AhoCorasick::START;
+        for chunk in ["xse", "v", "en"] {
+            for byte in chunk.bytes() {
+                state = automaton.step(state, byte);
+            }
+        }
+
+        assert_eq!(automaton.outputs(state), &[0]);
+        assert_eq!(automaton.pattern_len(0), 5);
+    }
+}
@@ -0,0 +1,612 @@
+use std::{error::Error, fmt::Display};
+
+use crate::day_02::{Bag, Color, Game, Round};
+
+/// The most bags [`Posterior::new`] is willing to consider.
+pub const MAX_BAGS: u128 = 1_000_000;
+
+/// The largest total number of cubes a [`Prior`] may give weight to.
+pub const MAX_TOTAL: u32 = 100_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InferenceError {
+    /// A Poisson prior needs a positive, finite mean.
+    InvalidMean(f64),
+    /// The prior weight of `total` cubes is negative or not finite.
+    InvalidWeight { total: u32, weight: f64 },
+    /// The prior allows `bags` bags for the game, more than `limit`.
+    TooManyBags { bags: u128, limit: u128 },
+    /// The prior reaches totals of `max` cubes, more than `limit`.
+    TotalTooLarge { max: u64, limit: u32 },
+}
+
+impl Display for InferenceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InferenceError::InvalidMean(mean) => {
+                write!(f, "a Poisson prior needs a positive mean, got {mean}")
+            }
+            InferenceError::InvalidWeight { total, weight } => write!(
+                f,
+                "the prior weight of {total} cubes has to be finite and not negative, got {weight}"
+            ),
+            InferenceError::TooManyBags { bags, limit } => write!(
+                f,
+                "the prior allows {bags} bags, but at most {limit} can be considered"
+            ),
+            InferenceError::TotalTooLarge { max, limit } => write!(
+                f,
+                "the prior reaches {max} cubes, but at most {limit} are supported"
+            ),
+        }
+    }
+}
+
+impl Error for InferenceError {}
+
+/// Prior belief about how many cubes are in the bag in total. Bags with the same total
+/// are considered equally likely.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prior {
+    /// The (unnormalised) weight of every total, starting at 0.
+    weights: Vec<f64>,
+}
+
+impl Prior {
+    /// Every total from 0 to `max` is equally likely. `max` may be at most [`MAX_TOTAL`].
+    pub fn uniform(max: u32) -> Result<Self, InferenceError> {
+        check_max(u64::from(max))?;
+        Ok(Prior {
+            weights: vec![1.0; max as usize + 1],
+        })
+    }
+
+    /// Totals follow a Poisson distribution around `mean`, cut off after `max`. `max` may
+    /// be at most [`MAX_TOTAL`].
+    pub fn poisson(mean: f64, max: u32) -> Result<Self, InferenceError> {
+        if !mean.is_finite() || mean <= 0.0 {
+            return Err(InferenceError::InvalidMean(mean));
+        }
+        check_max(u64::from(max))?;
+
+        let mut ln_factorial = 0.0;
+        let weights = (0..=max)
+            .map(|total| {
+                if total > 0 {
+                    ln_factorial += (total as f64).ln();
+                }
+                (total as f64 * mean.ln() - mean - ln_factorial).exp()
+            })
+            .collect();
+        Ok(Prior { weights })
+    }
+
+    /// Weights for the totals `0, 1, 2, ...`; totals past the end have weight 0. At most
+    /// the totals up to [`MAX_TOTAL`] may be given.
+    pub fn from_weights(weights: Vec<f64>) -> Result<Self, InferenceError> {
+        if let Some(max) = (weights.len() as u64).checked_sub(1) {
+            check_max(max)?;
+        }
+        if let Some((total, weight)) = weights
+            .iter()
+            .enumerate()
+            .find(|(_, weight)| !weight.is_finite() || **weight < 0.0)
+        {
+            return Err(InferenceError::InvalidWeight {
+                total: total as u32,
+                weight: *weight,
+            });
+        }
+        Ok(Prior { weights })
+    }
+
+    pub fn max(&self) -> u32 {
+        self.weights.len().saturating_sub(1) as u32
+    }
+
+    fn weight(&self, total: u32) -> f64 {
+        self.weights.get(total as usize).copied().unwrap_or(0.0)
+    }
+}
+
+fn check_max(max: u64) -> Result<(), InferenceError> {
+    if max > u64::from(MAX_TOTAL) {
+        return Err(InferenceError::TotalTooLarge {
+            max,
+            limit: MAX_TOTAL,
+        });
+    }
+    Ok(())
+}
+
+/// `ln C(n, k)`, as a product of `min(k, n - k)` factors so that huge `n` cost nothing extra.
+fn ln_binomial(n: u64, k: u64) -> f64 {
+    let Some(rest) = n.checked_sub(k) else {
+        return f64::NEG_INFINITY;
+    };
+    let k = k.min(rest);
+    (1..=k)
+        .map(|i| ((n - k + i) as f64).ln() - (i as f64).ln())
+        .sum()
+}
+
+/// How many cubes of `color` `round` shows, in all its draws together.
+fn drawn(round: &Round, color: &Color) -> u64 {
+    round
+        .information()
+        .iter()
+        .filter(|information| information.color() == color)
+        .map(|information| u64::from(information.amount()))
+        .sum()
+}
+
+/// Log-likelihood of `game` for a bag holding `counts[i]` cubes of `colors[i]`. Every
+/// round draws its cubes uniformly without replacement and puts them back afterwards.
+///
+/// A round that shows more cubes of a colour than the bag holds, or a colour the bag
+/// does not hold at all, makes the game impossible.
+fn ln_likelihood(game: &Game, colors: &[Color], counts: &[u32]) -> f64 {
+    let total = counts.iter().copied().map(u64::from).sum::<u64>();
+
+    let mut ln_likelihood = 0.0;
+    for round in game.rounds() {
+        let mut cubes = 0;
+        for (color, count) in colors.iter().zip(counts) {
+            let drawn = drawn(round, color);
+            if drawn > u64::from(*count) {
+                return f64::NEG_INFINITY;
+            }
+            ln_likelihood += ln_binomial(u64::from(*count), drawn);
+            cubes += drawn;
+        }
+
+        let shown = round
+            .information()
+            .iter()
+            .map(|information| u64::from(information.amount()))
+            .sum::<u64>();
+        if shown > cubes {
+            return f64::NEG_INFINITY;
+        }
+        ln_likelihood -= ln_binomial(total, cubes);
+    }
+    ln_likelihood
+}
+
+/// The probability that drawing from `bag` gives exactly the rounds of `game`.
+pub fn likelihood(game: &Game, bag: &Bag) -> f64 {
+    let colors = bag.colors().cloned().collect::<Vec<_>>();
+    let counts = colors
+        .iter()
+        .map(|color| bag.count(color))
+        .collect::<Vec<_>>();
+
+    ln_likelihood(game, &colors, &counts).exp()
+}
+
+/// The posterior distribution over the bags that could have produced a game.
+///
+/// Only the colours shown in the game are considered, and only bags up to the largest
+/// total the prior allows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Posterior {
+    colors: Vec<Color>,
+    /// The counts of every bag considered, one number per colour and bag.
+    counts: Vec<u32>,
+    probabilities: Vec<f64>,
+}
+
+impl Posterior {
+    /// The posterior for `game`, refusing priors that allow more than [`MAX_BAGS`] bags.
+    pub fn new(game: &Game, prior: &Prior) -> Result<Self, InferenceError> {
+        Posterior::with_limit(game, prior, MAX_BAGS)
+    }
+
+    /// Like [`Posterior::new`], but with a custom limit on the number of bags.
+    pub fn with_limit(game: &Game, prior: &Prior, limit: u128) -> Result<Self, InferenceError> {
+        let mut colors = game
+            .information()
+            .map(|information| information.color().clone())
+            .collect::<Vec<_>>();
+        colors.sort();
+        colors.dedup();
+
+        // a bag needs at least as many cubes of a colour as any round shows of it
+        let minimum = colors
+            .iter()
+            .map(|color| {
+                let most = game.rounds().iter().map(|round| drawn(round, color)).max();
+                u32::try_from(most.unwrap_or(0)).unwrap_or(u32::MAX)
+            })
+            .collect::<Vec<_>>();
+        let minimum_total = minimum.iter().copied().map(u64::from).sum::<u64>();
+        let minimum_total = u32::try_from(minimum_total).unwrap_or(u32::MAX);
+
+        let totals = (minimum_total..=prior.max()).filter(|total| prior.weight(*total) > 0.0);
+        let bags = totals
+            .clone()
+            .map(|total| count_compositions(total - minimum_total, colors.len()))
+            .fold(0, u128::saturating_add);
+        if bags > limit {
+            return Err(InferenceError::TooManyBags { bags, limit });
+        }
+
+        let parts = colors.len() as u64;
+        let mut counts = vec![];
+        let mut probabilities = vec![];
+
+        for total in totals {
+            // all ways of splitting `total` over the colours are equally likely
+            let splits = ln_binomial(
+                (u64::from(total) + parts).saturating_sub(1),
+                u64::from(total),
+            );
+            let ln_prior = prior.weight(total).ln() - splits;
+
+            for_each_composition(total, &minimum, &mut |bag| {
+                counts.extend_from_slice(bag);
+                probabilities.push(ln_prior + ln_likelihood(game, &colors, bag));
+            });
+        }
+
+        // normalise in log space first, the raw likelihoods easily underflow
+        let max = probabilities
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+        for p in &mut probabilities {
+            *p = (*p - max).exp();
+        }
+        let sum = probabilities.iter().sum::<f64>();
+        for p in &mut probabilities {
+            *p /= sum;
+        }
+
+        Ok(Posterior {
+            colors,
+            counts,
+            probabilities,
+        })
+    }
+
+    /// The counts of the `index`th bag.
+    fn counts(&self, index: usize) -> &[u32] {
+        let parts = self.colors.len();
+        &self.counts[index * parts..(index + 1) * parts]
+    }
+
+    fn to_bag(&self, counts: &[u32]) -> Bag {
+        self.colors
+            .iter()
+            .cloned()
+            .zip(counts.iter().copied())
+            .collect()
+    }
+
+    /// The bag with the highest posterior probability, `None` if the prior rules out every bag.
+    pub fn most_likely(&self) -> Option<Bag> {
+        self.probabilities
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, _)| self.to_bag(self.counts(index)))
+    }
+
+    /// The posterior probability of `bag`. Bags with colours the game never showed have probability 0.
+    pub fn probability(&self, bag: &Bag) -> f64 {
+        if bag
+            .colors()
+            .any(|color| bag.count(color) > 0 && !self.colors.contains(color))
+        {
+            return 0.0;
+        }
+
+        let counts = self
+            .colors
+            .iter()
+            .map(|color| bag.count(color))
+            .collect::<Vec<_>>();
+        (0..self.probabilities.len())
+            .find(|index| self.counts(*index) == counts)
+            .map(|index| self.probabilities[index])
+            .unwrap_or(0.0)
+    }
+
+    /// The equal-tailed interval that holds the number of `color` cubes with probability
+    /// `confidence` (e.g. `0.95`).
+    pub fn interval(&self, color: &Color, confidence: f64) -> Option<(u32, u32)> {
+        let position = self.colors.iter().position(|c| c == color)?;
+
+        let mut marginal = vec![];
+        for (index, p) in self.probabilities.iter().enumerate() {
+            let count = self.counts(index)[position] as usize;
+            if marginal.len() <= count {
+                marginal.resize(count + 1, 0.0);
+            }
+            marginal[count] += p;
+        }
+
+        let tail = (1.0 - confidence) / 2.0;
+        let mut cumulative = 0.0;
+        let mut lower = None;
+        for (count, p) in marginal.iter().enumerate() {
+            cumulative += p;
+            if lower.is_none() && cumulative > tail {
+                lower = Some(count as u32);
+            }
+            // leave some room for rounding errors in the sum
+            if cumulative >= 1.0 - tail - 1e-12 {
+                return lower.map(|lower| (lower, count as u32));
+            }
+        }
+
+        lower.map(|lower| (lower, marginal.len() as u32 - 1))
+    }
+}
+
+/// The number of ways to hand out `spare` cubes to `parts` colours.
+fn count_compositions(spare: u32, parts: usize) -> u128 {
+    let Some(bars) = parts.checked_sub(1) else {
+        return u128::from(spare == 0);
+    };
+
+    // C(spare + bars, bars), one factor at a time so every step stays exact
+    (1..=bars as u128)
+        .try_fold(1u128, |ways, i| {
+            ways.checked_mul(spare as u128 + i).map(|ways| ways / i)
+        })
+        .unwrap_or(u128::MAX)
+}
+
+/// Calls `visit` with every way of splitting `total` into `minimum.len()` parts, each at
+/// least its minimum. The splits share one buffer, so nothing is allocated per split.
+fn for_each_composition(total: u32, minimum: &[u32], visit: &mut impl FnMut(&[u32])) {
+    let minimum_total = minimum.iter().copied().map(u64::from).sum::<u64>();
+    let Some(spare) = u64::from(total).checked_sub(minimum_total) else {
+        return;
+    };
+    fill(spare as u32, minimum, &mut minimum.to_vec(), 0, visit);
+}
+
+fn fill(
+    spare: u32,
+    minimum: &[u32],
+    counts: &mut [u32],
+    index: usize,
+    visit: &mut impl FnMut(&[u32]),
+) {
+    match counts.len() - index {
+        0 if spare == 0 => visit(counts),
+        0 => {}
+        // the last colour takes whatever is left
+        1 => {
+            counts[index] = minimum[index] + spare;
+            visit(counts);
+        }
+        _ => {
+            for extra in 0..=spare {
+                counts[index] = minimum[index] + extra;
+                fill(spare - extra, minimum, counts, index + 1, visit);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn bag(cubes: &[(&str, u32)]) -> Bag {
+        cubes
+            .iter()
+            .map(|(name, count)| (name.parse().unwrap(), *count))
+            .collect()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn test_likelihood() {
+        let bag = bag(&[("red", 2), ("blue", 1)]);
+
+        let game = Game::from_str("Game 1: 1 red").unwrap();
+        assert_close(likelihood(&game, &bag), 2.0 / 3.0);
+
+        let game = Game::from_str("Game 1: 1 red, 1 blue; 1 blue").unwrap();
+        assert_close(likelihood(&game, &bag), 2.0 / 3.0 * 1.0 / 3.0);
+
+        let game = Game::from_str("Game 1: 2 blue").unwrap();
+        assert_eq!(likelihood(&game, &bag), 0.0);
+        let game = Game::from_str("Game 1: 1 green").unwrap();
+        assert_eq!(likelihood(&game, &bag), 0.0);
+    }
+
+    #[test]
+    fn test_impossible_and_huge_bags() {
+        let game = Game::from_str("Game 1: 2 red").unwrap();
+        assert_eq!(likelihood(&game, &bag(&[("red", 1)])), 0.0);
+        assert_eq!(likelihood(&game, &Bag::default()), 0.0);
+
+        // draws of the same colour within a round come out of the bag together
+        let game = Game::from_str("Game 1: 2 red, 3 red").unwrap();
+        assert_eq!(likelihood(&game, &bag(&[("red", 4)])), 0.0);
+        assert_close(likelihood(&game, &bag(&[("red", 5)])), 1.0);
+
+        let game = Game::from_str("Game 1: 1 red").unwrap();
+        let huge = bag(&[("red", 3_000_000_000), ("blue", 2_000_000_000)]);
+        assert_close(likelihood(&game, &huge), 0.6);
+    }
+
+    #[test]
+    fn test_posterior() {
+        let game = Game::from_str("Game 1: 1 red, 1 blue").unwrap();
+        let posterior = Posterior::new(&game, &Prior::uniform(4).unwrap()).unwrap();
+
+        // every total has the same mass, and within a total the bags are weighted by
+        // their likelihood: (1, 1) alone for 2 cubes, (1, 2) and (2, 1) for 3 cubes, ...
+        assert_close(
+            posterior.probability(&bag(&[("red", 1), ("blue", 1)])),
+            1.0 / 3.0,
+        );
+        assert_close(
+            posterior.probability(&bag(&[("red", 2), ("blue", 1)])),
+            1.0 / 6.0,
+        );
+        assert_close(
+            posterior.probability(&bag(&[("red", 2), ("blue", 2)])),
+            0.4 / 3.0,
+        );
+        assert_eq!(posterior.probability(&bag(&[("red", 5), ("blue", 1)])), 0.0);
+        assert_eq!(
+            posterior.probability(&bag(&[("red", 1), ("blue", 1), ("green", 1)])),
+            0.0
+        );
+
+        assert_eq!(
+            posterior.most_likely(),
+            Some(bag(&[("red", 1), ("blue", 1)]))
+        );
+
+        let red = "red".parse().unwrap();
+        assert_eq!(posterior.interval(&red, 0.9), Some((1, 3)));
+        assert_eq!(posterior.interval(&red, 0.5), Some((1, 2)));
+        assert_eq!(posterior.interval(&"green".parse().unwrap(), 0.9), None);
+    }
+
+    #[test]
+    fn test_repeated_colour() {
+        // the bag has to hold both draws of red in the first round at once
+        let game = Game::from_str("Game 1: 2 red, 3 red; 4 red").unwrap();
+        let posterior = Posterior::new(&game, &Prior::uniform(6).unwrap()).unwrap();
+
+        // with a single colour every bag big enough explains the game equally well
+        assert_eq!(posterior.probability(&bag(&[("red", 4)])), 0.0);
+        assert_close(posterior.probability(&bag(&[("red", 5)])), 0.5);
+        assert_close(posterior.probability(&bag(&[("red", 6)])), 0.5);
+    }
+
+    #[test]
+    fn test_prior_moves_estimate() {
+        // many rounds of one red and one blue make equal counts the most likely,
+        // while the prior decides how many cubes there are
+        let game = Game::from_str("Game 1: 1 red, 1 blue; 1 red, 1 blue; 1 red, 1 blue").unwrap();
+
+        let small = Posterior::new(&game, &Prior::poisson(2.0, 30).unwrap()).unwrap();
+        assert_eq!(small.most_likely(), Some(bag(&[("red", 1), ("blue", 1)])));
+
+        let large = Posterior::new(&game, &Prior::poisson(20.0, 30).unwrap()).unwrap();
+        let most_likely = large.most_likely().unwrap();
+        let (red, blue) = (
+            most_likely.count(&"red".parse().unwrap()),
+            most_likely.count(&"blue".parse().unwrap()),
+        );
+        assert_eq!(red, blue);
+        assert!(red + blue > 10);
+
+        assert_eq!(
+            Posterior::new(&game, &Prior::uniform(1).unwrap())
+                .unwrap()
+                .most_likely(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_puzzle_game() {
+        let game =
+            Game::from_str("Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green").unwrap();
+        let posterior = Posterior::new(&game, &Prior::uniform(20).unwrap()).unwrap();
+
+        let total = posterior.probabilities.iter().sum::<f64>();
+        assert_close(total, 1.0);
+        assert!(posterior.probability(&game.minimal_bag()) > 0.0);
+
+        let (low, high) = posterior.interval(&"blue".parse().unwrap(), 0.95).unwrap();
+        assert!(6 <= low && low <= high);
+    }
+
+    #[test]
+    fn test_invalid_priors() {
+        assert_eq!(
+            Prior::poisson(0.0, 10),
+            Err(InferenceError::InvalidMean(0.0))
+        );
+        assert!(Prior::poisson(-1.0, 10).is_err());
+        assert!(Prior::poisson(f64::NAN, 10).is_err());
+        assert!(Prior::poisson(f64::INFINITY, 10).is_err());
+
+        assert_eq!(
+            Prior::from_weights(vec![1.0, -0.5]),
+            Err(InferenceError::InvalidWeight {
+                total: 1,
+                weight: -0.5
+            })
+        );
+        assert!(Prior::from_weights(vec![f64::NAN]).is_err());
+        assert!(Prior::from_weights(vec![0.0, 2.0]).is_ok());
+
+        assert!(Prior::uniform(MAX_TOTAL).is_ok());
+        let err = Prior::uniform(MAX_TOTAL + 1).unwrap_err();
+        assert_eq!(
+            err,
+            InferenceError::TotalTooLarge {
+                max: u64::from(MAX_TOTAL) + 1,
+                limit: MAX_TOTAL
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "the prior reaches 100001 cubes, but at most 100000 are supported"
+        );
+        assert!(Prior::poisson(2.0, u32::MAX).is_err());
+    }
+
+    #[test]
+    fn test_too_many_bags() {
+        let game = Game::from_str("Game 1: 1 red, 1 blue, 1 green, 1 yellow, 1 white").unwrap();
+
+        // C(100, 5) ways to fill a bag of up to 100 cubes with at least one of each colour
+        let err = Posterior::new(&game, &Prior::uniform(100).unwrap()).unwrap_err();
+        assert_eq!(
+            err,
+            InferenceError::TooManyBags {
+                bags: 75_287_520,
+                limit: MAX_BAGS
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "the prior allows 75287520 bags, but at most 1000000 can be considered"
+        );
+
+        assert!(Posterior::with_limit(&game, &Prior::uniform(10).unwrap(), 252).is_ok());
+        assert!(Posterior::with_limit(&game, &Prior::uniform(10).unwrap(), 251).is_err());
+    }
+
+    #[test]
+    fn test_compositions() {
+        let compositions = |total, minimum: &[u32]| {
+            let mut all = vec![];
+            for_each_composition(total, minimum, &mut |counts| all.push(counts.to_vec()));
+            all
+        };
+
+        assert_eq!(compositions(4, &[1, 2]), vec![vec![1, 3], vec![2, 2]]);
+        assert_eq!(compositions(2, &[2, 1]), Vec::<Vec<u32>>::new());
+        assert_eq!(compositions(0, &[]), vec![Vec::<u32>::new()]);
+        assert_eq!(compositions(1, &[]), Vec::<Vec<u32>>::new());
+        assert_eq!(compositions(7, &[1, 0, 2]).len(), 15);
+
+        assert_eq!(count_compositions(4, 3), 15);
+        assert_eq!(count_compositions(0, 0), 1);
+        assert_eq!(count_compositions(1, 0), 0);
+        assert_eq!(count_compositions(u32::MAX, 20), u128::MAX);
+    }
+}
@@ -0,0 +1,287 @@
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    iter::Product,
+    ops::{Add, Mul, Sub},
+    str::FromStr,
+};
+
+/// An arbitrary-precision unsigned integer, stored as little-endian base 2^32 limbs
+/// without trailing zero limbs.
+#[derive(Debug, Default, Clone, Hash, PartialEq, Eq)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    fn normalize(mut self) -> Self {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        self
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.limbs.as_slice() {
+            [] => Some(0),
+            [low] => Some(*low as u64),
+            [low, high] => Some((*high as u64) << 32 | *low as u64),
+            _ => None,
+        }
+    }
+
+    pub fn bits(&self) -> u64 {
+        match self.limbs.last() {
+            Some(high) => self.limbs.len() as u64 * 32 - high.leading_zeros() as u64,
+            None => 0,
+        }
+    }
+
+    fn set_bit(&mut self, bit: u64) {
+        let limb = (bit / 32) as usize;
+        if self.limbs.len() <= limb {
+            self.limbs.resize(limb + 1, 0);
+        }
+        self.limbs[limb] |= 1 << (bit % 32);
+    }
+
+    pub fn checked_sub(&self, other: &BigUint) -> Option<BigUint> {
+        if self < other {
+            return None;
+        }
+
+        let mut limbs = Vec::with_capacity(self.limbs.len());
+        let mut borrow = 0i64;
+
+        for (i, limb) in self.limbs.iter().enumerate() {
+            let mut diff = *limb as i64 - other.limbs.get(i).cloned().unwrap_or(0) as i64 - borrow;
+            borrow = 0;
+            if diff < 0 {
+                diff += 1 << 32;
+                borrow = 1;
+            }
+            limbs.push(diff as u32);
+        }
+
+        Some(BigUint { limbs }.normalize())
+    }
+
+    /// Divides by a small divisor, returning the quotient and the remainder.
+    pub fn div_rem_small(&self, divisor: u32) -> (BigUint, u32) {
+        let mut limbs = vec![0; self.limbs.len()];
+        let mut rem = 0u64;
+
+        for (i, limb) in self.limbs.iter().enumerate().rev() {
+            let cur = rem << 32 | *limb as u64;
+            limbs[i] = (cur / divisor as u64) as u32;
+            rem = cur % divisor as u64;
+        }
+
+        (BigUint { limbs }.normalize(), rem as u32)
+    }
+
+    /// The largest integer whose square doesn't exceed `self`, found bit by bit from the top.
+    pub fn isqrt(&self) -> BigUint {
+        let mut root = BigUint::default();
+
+        for bit in (0..self.bits().div_ceil(2)).rev() {
+            let mut candidate = root.clone();
+            candidate.set_bit(bit);
+            if &(&candidate * &candidate) <= self {
+                root = candidate;
+            }
+        }
+
+        root
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(value: u64) -> Self {
+        BigUint {
+            limbs: vec![value as u32, (value >> 32) as u32],
+        }
+        .normalize()
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl Add for &BigUint {
+    type Output = BigUint;
+
+    fn add(self, other: &BigUint) -> BigUint {
+        let len = self.limbs.len().max(other.limbs.len());
+        let mut limbs = Vec::with_capacity(len + 1);
+        let mut carry = 0u64;
+
+        for i in 0..len {
+            let sum = self.limbs.get(i).cloned().unwrap_or(0) as u64
+                + other.limbs.get(i).cloned().unwrap_or(0) as u64
+                + carry;
+            limbs.push(sum as u32);
+            carry = sum >> 32;
+        }
+        limbs.push(carry as u32);
+
+        BigUint { limbs }.normalize()
+    }
+}
+
+impl Sub for &BigUint {
+    type Output = BigUint;
+
+    fn sub(self, other: &BigUint) -> BigUint {
+        self.checked_sub(other)
+            .expect("attempt to subtract with overflow")
+    }
+}
+
+impl Mul for &BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: &BigUint) -> BigUint {
+        let mut limbs = vec![0u32; self.limbs.len() + other.limbs.len()];
+
+        for (i, a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, b) in other.limbs.iter().enumerate() {
+                let cur = limbs[i + j] as u64 + *a as u64 * *b as u64 + carry;
+                limbs[i + j] = cur as u32;
+                carry = cur >> 32;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+
+        BigUint { limbs }.normalize()
+    }
+}
+
+impl Product for BigUint {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(BigUint::from(1), |memo, cur| &memo * &cur)
+    }
+}
+
+impl FromStr for BigUint {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(());
+        }
+
+        let ten = BigUint::from(10);
+        let mut value = BigUint::default();
+
+        for c in s.chars() {
+            let digit = c.to_digit(10).ok_or(())?;
+            value = &(&value * &ten) + &BigUint::from(digit as u64);
+        }
+
+        Ok(value)
+    }
+}
+
+impl Display for BigUint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_zero() {
+            return f.write_str("0");
+        }
+
+        let mut chunks = vec![];
+        let mut rest = self.clone();
+        while !rest.is_zero() {
+            let (quotient, rem) = rest.div_rem_small(1_000_000_000);
+            chunks.push(rem);
+            rest = quotient;
+        }
+
+        let mut chunks = chunks.into_iter().rev();
+        write!(f, "{}", chunks.next().unwrap_or(0))?;
+        for chunk in chunks {
+            write!(f, "{chunk:09}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(s: &str) -> BigUint {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_display() {
+        for s in [
+            "0",
+            "7",
+            "4294967296",
+            "18446744073709551616",
+            "123456789012345678901234567890",
+        ] {
+            assert_eq!(big(s).to_string(), s);
+        }
+        assert_eq!(big("000042").to_string(), "42");
+        assert_eq!("".parse::<BigUint>(), Err(()));
+        assert_eq!("12a".parse::<BigUint>(), Err(()));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let a = big("123456789012345678901234567890");
+        let b = big("987654321098765432109876543210");
+
+        assert_eq!((&a + &b).to_string(), "1111111110111111111011111111100");
+        assert_eq!((&b - &a).to_string(), "864197532086419753208641975320");
+        assert_eq!(
+            (&a * &b).to_string(),
+            "121932631137021795226185032733622923332237463801111263526900"
+        );
+        assert_eq!(a.checked_sub(&b), None);
+        assert_eq!(&a - &a, BigUint::default());
+        assert!(a < b);
+    }
+
+    #[test]
+    fn test_u64_conversions() {
+        assert_eq!(BigUint::from(u64::MAX).to_u64(), Some(u64::MAX));
+        assert_eq!(BigUint::from(0).to_u64(), Some(0));
+        assert_eq!(big("18446744073709551616").to_u64(), None);
+    }
+
+    #[test]
+    fn test_isqrt() {
+        for n in 0..2000u64 {
+            assert_eq!(BigUint::from(n).isqrt(), BigUint::from(n.isqrt()));
+        }
+
+        let root = big("1000000000000000000000000000000");
+        let square = &root * &root;
+        assert_eq!(square.isqrt(), root);
+        assert_eq!(
+            (&square - &BigUint::from(1)).isqrt(),
+            &root - &BigUint::from(1)
+        );
+    }
+}
@@ -1,6 +1,21 @@
+use std::{
+    error::Error,
+    fmt::Display,
+    fs,
+    io::{self, BufRead},
+    path::Path,
+    str::FromStr,
+};
+
 use aoc_runner_derive::{aoc, aoc_generator};
 
-type CalibrationNumber = u32;
+use crate::{
+    aho_corasick::{AhoCorasick, Match},
+    json,
+    parse_error::{parse_lines, ParseError},
+};
+
+pub type CalibrationNumber = u32;
 
 #[aoc_generator(day1, part1)]
 fn generator_day01_part1(inp: &str) -> Vec<Vec<CalibrationNumber>> {
@@ -13,51 +28,784 @@ fn generator_day01_part1(inp: &str) -> Vec<Vec<CalibrationNumber>> {
     cleared_lines
 }
 
-const REPLACE: [(&str, u32); 18] = [
-    ("one", 1),
+const DIGITS: [(&str, CalibrationNumber); 9] = [
     ("1", 1),
-    ("two", 2),
     ("2", 2),
-    ("three", 3),
     ("3", 3),
-    ("four", 4),
     ("4", 4),
-    ("five", 5),
     ("5", 5),
-    ("six", 6),
     ("6", 6),
-    ("seven", 7),
     ("7", 7),
-    ("eight", 8),
     ("8", 8),
-    ("nine", 9),
     ("9", 9),
 ];
 
+const ENGLISH: [(&str, CalibrationNumber); 9] = [
+    ("one", 1),
+    ("two", 2),
+    ("three", 3),
+    ("four", 4),
+    ("five", 5),
+    ("six", 6),
+    ("seven", 7),
+    ("eight", 8),
+    ("nine", 9),
+];
+
+const GERMAN: [(&str, CalibrationNumber); 9] = [
+    ("eins", 1),
+    ("zwei", 2),
+    ("drei", 3),
+    ("vier", 4),
+    ("fünf", 5),
+    ("sechs", 6),
+    ("sieben", 7),
+    ("acht", 8),
+    ("neun", 9),
+];
+
+const FRENCH: [(&str, CalibrationNumber); 9] = [
+    ("un", 1),
+    ("deux", 2),
+    ("trois", 3),
+    ("quatre", 4),
+    ("cinq", 5),
+    ("six", 6),
+    ("sept", 7),
+    ("huit", 8),
+    ("neuf", 9),
+];
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Language {
+    English,
+    German,
+    French,
+}
+
+#[derive(Debug)]
+pub enum LexiconError {
+    Io(io::Error),
+    Parse(ParseError),
+}
+
+impl Display for LexiconError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LexiconError::Io(err) => write!(f, "could not read lexicon: {err}"),
+            LexiconError::Parse(err) => write!(f, "invalid lexicon: {err}"),
+        }
+    }
+}
+
+impl Error for LexiconError {}
+
+/// The words that count as digits in a calibration line, each with its value.
+///
+/// Entries are tried together in a single pass. When two entries start at the same
+/// position, the one that was added first counts as the first digit and the one added
+/// last as the last digit.
+#[derive(Debug, Clone)]
+pub struct Lexicon {
+    entries: Vec<(String, CalibrationNumber)>,
+    matcher: AhoCorasick,
+}
+
+impl PartialEq for Lexicon {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries == other.entries
+    }
+}
+
+impl Eq for Lexicon {}
+
+impl Lexicon {
+    pub fn new(entries: Vec<(String, CalibrationNumber)>) -> Self {
+        let matcher = AhoCorasick::new(entries.iter().map(|(word, _)| word));
+        Lexicon { entries, matcher }
+    }
+
+    /// Only the digits `1` to `9`.
+    pub fn digits() -> Self {
+        Lexicon::new(
+            DIGITS
+                .iter()
+                .map(|(word, n)| (word.to_string(), *n))
+                .collect(),
+        )
+    }
+
+    /// The digits `1` to `9` and the number words for them in `language`.
+    pub fn language(language: Language) -> Self {
+        let words = match language {
+            Language::English => ENGLISH,
+            Language::German => GERMAN,
+            Language::French => FRENCH,
+        };
+
+        Lexicon::new(
+            words
+                .iter()
+                .zip(DIGITS)
+                .flat_map(|(word, digit)| [*word, digit])
+                .map(|(word, n)| (word.to_string(), n))
+                .collect(),
+        )
+    }
+
+    /// Combines two lexicons. Words that appear in both take the value from `other`.
+    pub fn merge(&self, other: &Lexicon) -> Self {
+        let mut entries = self
+            .entries
+            .iter()
+            .filter(|(word, _)| other.entries.iter().all(|(other, _)| other != word))
+            .cloned()
+            .collect::<Vec<_>>();
+        entries.extend(other.entries.iter().cloned());
+
+        Lexicon::new(entries)
+    }
+
+    /// Reads a lexicon from a file in the format accepted by [`Lexicon::from_str`].
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, LexiconError> {
+        fs::read_to_string(path)
+            .map_err(LexiconError::Io)?
+            .parse()
+            .map_err(LexiconError::Parse)
+    }
+
+    pub fn entries(&self) -> &[(String, CalibrationNumber)] {
+        &self.entries
+    }
+
+    fn matcher(&self) -> &AhoCorasick {
+        &self.matcher
+    }
+}
+
+impl FromStr for Lexicon {
+    type Err = ParseError;
+
+    /// Parses one `word digit` pair per line. Blank lines and lines starting with `#` are skipped.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut entries = vec![];
+
+        for (i, line) in s.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = trimmed.split_whitespace();
+            let word = parts.next().unwrap_or(trimmed);
+            let digit = match parts.next() {
+                Some(digit) => digit
+                    .parse::<CalibrationNumber>()
+                    .ok()
+                    .filter(|digit| *digit <= 9)
+                    .ok_or_else(|| ParseError::at(line, digit, "a digit from 0 to 9")),
+                None => Err(ParseError::at_end(line, "a digit after the word")),
+            };
+            let digit = digit.map_err(|err| err.on_line(i + 1, line))?;
+
+            if let Some(rest) = parts.next() {
+                return Err(ParseError::at(line, rest, "the end of the line").on_line(i + 1, line));
+            }
+
+            entries.push((word.to_string(), digit));
+        }
+
+        Ok(Lexicon::new(entries))
+    }
+}
+
+/// A lexicon entry found in a calibration line, spanning the bytes `start..end`.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Token {
+    pub start: usize,
+    pub end: usize,
+    /// The index of the entry in [`Lexicon::entries`].
+    pub entry: usize,
+}
+
+/// Every token of a calibration line, together with the ones picked as its first and last digit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace<'a> {
+    line: &'a str,
+    lexicon: &'a Lexicon,
+    tokens: Vec<Token>,
+    first: Option<usize>,
+    last: Option<usize>,
+}
+
+impl<'a> Trace<'a> {
+    /// Finds all tokens of `line`. Tokens may overlap (`eightwo` is 8 and 2); the first
+    /// digit is the token that starts first, the last digit the token that starts last.
+    pub fn new(line: &'a str, lexicon: &'a Lexicon) -> Self {
+        let mut tokens = lexicon
+            .matcher()
+            .find_overlapping(line.as_bytes())
+            .map(
+                |Match {
+                     pattern,
+                     start,
+                     end,
+                 }| Token {
+                    start,
+                    end,
+                    entry: pattern,
+                },
+            )
+            .collect::<Vec<_>>();
+        tokens.sort_by_key(|token| (token.start, token.entry));
+
+        let first = (!tokens.is_empty()).then_some(0);
+        let last = tokens.len().checked_sub(1);
+
+        Trace {
+            line,
+            lexicon,
+            tokens,
+            first,
+            last,
+        }
+    }
+
+    pub fn line(&self) -> &str {
+        self.line
+    }
+
+    /// All tokens, ordered by their start.
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    pub fn first(&self) -> Option<&Token> {
+        self.first.map(|index| &self.tokens[index])
+    }
+
+    pub fn last(&self) -> Option<&Token> {
+        self.last.map(|index| &self.tokens[index])
+    }
+
+    pub fn word(&self, token: &Token) -> &str {
+        &self.lexicon.entries[token.entry].0
+    }
+
+    pub fn value(&self, token: &Token) -> CalibrationNumber {
+        self.lexicon.entries[token.entry].1
+    }
+
+    /// The first and last digit, if the line has any.
+    pub fn calibration(&self) -> Option<(CalibrationNumber, CalibrationNumber)> {
+        self.first()
+            .zip(self.last())
+            .map(|(first, last)| (self.value(first), self.value(last)))
+    }
+
+    /// The trace as a JSON object with the line and its tokens.
+    pub fn to_json(&self) -> String {
+        let tokens = self.tokens.iter().enumerate().map(|(index, token)| {
+            json::object([
+                ("start", token.start.to_string()),
+                ("end", token.end.to_string()),
+                ("entry", token.entry.to_string()),
+                ("word", json::string(self.word(token))),
+                ("value", self.value(token).to_string()),
+                ("first", (self.first == Some(index)).to_string()),
+                ("last", (self.last == Some(index)).to_string()),
+            ])
+        });
+
+        json::object([
+            ("line", json::string(self.line)),
+            ("tokens", json::array(tokens)),
+        ])
+    }
+}
+
+/// Renders the line with every token underlined on a row of its own:
+///
+/// ```text
+/// xtwone3four
+///  ^^^        two = 2 (first)
+///    ^^^      one = 1
+///       ^     3 = 3
+///        ^^^^ four = 4 (last)
+/// ```
+impl Display for Trace<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.line)?;
+
+        // columns count characters, not bytes
+        let column = |offset: usize| self.line[..offset].chars().count();
+        let width = self.line.chars().count();
+
+        for (index, token) in self.tokens.iter().enumerate() {
+            let (start, end) = (column(token.start), column(token.end));
+            write!(
+                f,
+                "\n{}{}{} {} = {}",
+                " ".repeat(start),
+                "^".repeat(end - start),
+                " ".repeat(width - end),
+                self.word(token),
+                self.value(token)
+            )?;
+
+            match (self.first == Some(index), self.last == Some(index)) {
+                (true, true) => write!(f, " (first, last)")?,
+                (true, false) => write!(f, " (first)")?,
+                (false, true) => write!(f, " (last)")?,
+                (false, false) => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Traces every line of `inp`, for finding out why a line was read the way it was.
+pub fn explain<'a>(inp: &'a str, lexicon: &'a Lexicon) -> Vec<Trace<'a>> {
+    inp.lines().map(|line| Trace::new(line, lexicon)).collect()
+}
+
+/// The traces of [`explain`] as a JSON array.
+pub fn explain_json(traces: &[Trace]) -> String {
+    json::array(traces.iter().map(Trace::to_json))
+}
+
+/// A way of reading the first and last number of a calibration line.
+pub trait Tokenizer {
+    /// The first and last number in `line`, `None` if there is none.
+    fn first_and_last(&self, line: &str) -> Option<(CalibrationNumber, CalibrationNumber)>;
+
+    /// What a line without any number is missing, for error messages.
+    fn expected(&self) -> &'static str;
+}
+
+impl Tokenizer for Lexicon {
+    fn first_and_last(&self, line: &str) -> Option<(CalibrationNumber, CalibrationNumber)> {
+        Trace::new(line, self).calibration()
+    }
+
+    fn expected(&self) -> &'static str {
+        "at least one digit or spelled-out digit"
+    }
+}
+
+fn parse_calibration_line(
+    line: &str,
+    tokenizer: &impl Tokenizer,
+) -> Result<(CalibrationNumber, CalibrationNumber), ParseError> {
+    tokenizer
+        .first_and_last(line)
+        .ok_or_else(|| ParseError::at(line, line, tokenizer.expected()))
+}
+
+/// Reads the first and last number of every line with `tokenizer`, which is usually a
+/// [`Lexicon`] of single digits or [`EnglishNumerals`] for multi-digit numbers.
+pub fn generator_day01_part2(
+    inp: &str,
+    tokenizer: &impl Tokenizer,
+) -> Result<Vec<(CalibrationNumber, CalibrationNumber)>, ParseError> {
+    parse_lines(1, inp, |line| parse_calibration_line(line, tokenizer))
+}
+
 #[aoc_generator(day1, part2)]
-fn generator_day01_part2(inp: &str) -> Vec<(CalibrationNumber, CalibrationNumber)> {
-    let mut pairs = vec![];
+fn generator_day01_part2_english(
+    inp: &str,
+) -> Result<Vec<(CalibrationNumber, CalibrationNumber)>, ParseError> {
+    generator_day01_part2(inp, &Lexicon::language(Language::English))
+}
 
-    for line in inp.lines() {
-        let mut first = None;
-        let mut last = None;
-        for i in 0..line.len() {
-            for (p, n) in REPLACE {
-                if line[i..].starts_with(p) {
-                    if first.is_none() {
-                        first = Some(n);
-                    }
-                    last = Some(n)
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+    /// The byte at `column` (0-based) of the 1-based `line` is not valid UTF-8.
+    InvalidUtf8 {
+        line: usize,
+        column: usize,
+    },
+    /// The 1-based `line` has no digit in it.
+    NoDigit {
+        line: usize,
+    },
+}
+
+impl Display for StreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamError::Io(err) => write!(f, "could not read calibration document: {err}"),
+            StreamError::InvalidUtf8 { line, column } => {
+                write!(f, "day 1, line {line}, byte {}: invalid UTF-8", column + 1)
+            }
+            StreamError::NoDigit { line } => write!(
+                f,
+                "day 1, line {line}: expected at least one digit or spelled-out digit"
+            ),
+        }
+    }
+}
+
+impl Error for StreamError {}
+
+/// The first or last digit seen so far on a line, as `(start, entry, value)`.
+type Candidate = (usize, usize, CalibrationNumber);
+
+/// Sums calibration values of input that arrives in chunks, in constant memory.
+///
+/// Chunks may split lines, UTF-8 sequences and number words anywhere; the reader only
+/// keeps the automaton state and the first and last digit of the current line.
+#[derive(Debug, Clone)]
+pub struct CalibrationReader<'a> {
+    lexicon: &'a Lexicon,
+    lenient: bool,
+    state: usize,
+    line: usize,
+    column: usize,
+    /// Lower and upper bound of the next byte and how many continuation bytes are still missing.
+    utf8: (u8, u8, u8),
+    first: Option<Candidate>,
+    last: Option<Candidate>,
+    sum: u64,
+}
+
+impl<'a> CalibrationReader<'a> {
+    pub fn new(lexicon: &'a Lexicon) -> Self {
+        CalibrationReader {
+            lexicon,
+            lenient: false,
+            state: AhoCorasick::START,
+            line: 1,
+            column: 0,
+            utf8: (0x80, 0xbf, 0),
+            first: None,
+            last: None,
+            sum: 0,
+        }
+    }
+
+    /// Counts lines without a digit as 0 instead of rejecting them, like part 1 does.
+    pub fn lenient(mut self) -> Self {
+        self.lenient = true;
+        self
+    }
+
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<(), StreamError> {
+        for byte in chunk {
+            self.validate(*byte)?;
+
+            if *byte == b'\n' {
+                self.end_line()?;
+                continue;
+            }
+
+            self.column += 1;
+            self.state = self.lexicon.matcher().step(self.state, *byte);
+
+            for pattern in self.lexicon.matcher().outputs(self.state) {
+                let start = self.column - self.lexicon.matcher().pattern_len(*pattern);
+                let candidate = (start, *pattern, self.lexicon.entries[*pattern].1);
+                let key = |(start, pattern, _): Candidate| (start, pattern);
+
+                if self.first.is_none_or(|first| key(candidate) < key(first)) {
+                    self.first = Some(candidate);
+                }
+                if self.last.is_none_or(|last| key(candidate) > key(last)) {
+                    self.last = Some(candidate);
                 }
             }
         }
-        pairs.push((
-            first.expect("invalid puzzle input"),
-            last.expect("invalid puzzle input"),
-        ));
+
+        Ok(())
     }
 
-    pairs
+    /// Finishes the last line, which does not need a trailing newline, and returns the sum.
+    pub fn finish(mut self) -> Result<u64, StreamError> {
+        if self.utf8.2 > 0 {
+            return Err(StreamError::InvalidUtf8 {
+                line: self.line,
+                column: self.column,
+            });
+        }
+        if self.column > 0 {
+            self.end_line()?;
+        }
+        Ok(self.sum)
+    }
+
+    fn validate(&mut self, byte: u8) -> Result<(), StreamError> {
+        let (low, high, missing) = self.utf8;
+
+        self.utf8 = match (missing, byte) {
+            (0, 0x00..=0x7f) => (0x80, 0xbf, 0),
+            (0, 0xc2..=0xdf) => (0x80, 0xbf, 1),
+            (0, 0xe0) => (0xa0, 0xbf, 2),
+            (0, 0xed) => (0x80, 0x9f, 2),
+            (0, 0xe1..=0xef) => (0x80, 0xbf, 2),
+            (0, 0xf0) => (0x90, 0xbf, 3),
+            (0, 0xf4) => (0x80, 0x8f, 3),
+            (0, 0xf1..=0xf3) => (0x80, 0xbf, 3),
+            (1.., byte) if (low..=high).contains(&byte) => (0x80, 0xbf, missing - 1),
+            _ => {
+                return Err(StreamError::InvalidUtf8 {
+                    line: self.line,
+                    column: self.column,
+                })
+            }
+        };
+
+        Ok(())
+    }
+
+    fn end_line(&mut self) -> Result<(), StreamError> {
+        match self.first.zip(self.last) {
+            Some(((_, _, first), (_, _, last))) => self.sum += calibration_value(first, last),
+            None if self.lenient => {}
+            None => return Err(StreamError::NoDigit { line: self.line }),
+        }
+
+        self.state = AhoCorasick::START;
+        self.line += 1;
+        self.column = 0;
+        self.first = None;
+        self.last = None;
+        Ok(())
+    }
+}
+
+/// Sums the calibration values of everything `reader` produces, without holding the input in memory.
+pub fn sum_calibration(reader: impl BufRead, lexicon: &Lexicon) -> Result<u64, StreamError> {
+    read_all(reader, CalibrationReader::new(lexicon))
+}
+
+/// Streaming version of part 1: only digits count and lines without one add nothing.
+pub fn sum_calibration_digits(reader: impl BufRead) -> Result<u64, StreamError> {
+    let digits = Lexicon::digits();
+    read_all(reader, CalibrationReader::new(&digits).lenient())
+}
+
+fn read_all(
+    mut reader: impl BufRead,
+    mut calibration: CalibrationReader,
+) -> Result<u64, StreamError> {
+    loop {
+        let chunk = match reader.fill_buf() {
+            Ok([]) => return calibration.finish(),
+            Ok(chunk) => chunk,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(StreamError::Io(err)),
+        };
+        let len = chunk.len();
+        calibration.feed(chunk)?;
+        reader.consume(len);
+    }
+}
+
+const TEENS: [(&str, CalibrationNumber); 10] = [
+    ("ten", 10),
+    ("eleven", 11),
+    ("twelve", 12),
+    ("thirteen", 13),
+    ("fourteen", 14),
+    ("fifteen", 15),
+    ("sixteen", 16),
+    ("seventeen", 17),
+    ("eighteen", 18),
+    ("nineteen", 19),
+];
+
+const TENS: [(&str, CalibrationNumber); 8] = [
+    ("twenty", 20),
+    ("thirty", 30),
+    ("forty", 40),
+    ("fifty", 50),
+    ("sixty", 60),
+    ("seventy", 70),
+    ("eighty", 80),
+    ("ninety", 90),
+];
+
+const SCALES: [(&str, CalibrationNumber); 2] = [("million", 1_000_000), ("thousand", 1_000)];
+
+/// An English numeral found in a calibration line, spanning the bytes `start..end`.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Numeral {
+    pub start: usize,
+    pub end: usize,
+    pub value: CalibrationNumber,
+}
+
+fn words_at<'a>(
+    line: &'a str,
+    pos: usize,
+    words: &'a [(&str, CalibrationNumber)],
+) -> impl Iterator<Item = (CalibrationNumber, usize)> + 'a {
+    words
+        .iter()
+        .filter(move |(word, _)| line[pos..].starts_with(word))
+        .map(move |(word, value)| (*value, pos + word.len()))
+}
+
+/// Positions where the next word of a compound numeral may start: right away, or
+/// after a single space or hyphen.
+fn separators(line: &str, pos: usize) -> impl Iterator<Item = usize> {
+    let skip = matches!(line.as_bytes().get(pos), Some(b' ' | b'-')).then_some(pos + 1);
+    [pos].into_iter().chain(skip)
+}
+
+fn below_hundred(line: &str, pos: usize) -> Vec<(CalibrationNumber, usize)> {
+    let mut numerals = words_at(line, pos, &ENGLISH)
+        .chain(words_at(line, pos, &TEENS))
+        .collect::<Vec<_>>();
+
+    for (tens, end) in words_at(line, pos, &TENS) {
+        numerals.push((tens, end));
+        for pos in separators(line, end) {
+            numerals.extend(words_at(line, pos, &ENGLISH).map(|(unit, end)| (tens + unit, end)));
+        }
+    }
+
+    numerals
+}
+
+fn below_thousand(line: &str, pos: usize) -> Vec<(CalibrationNumber, usize)> {
+    let mut numerals = below_hundred(line, pos);
+
+    for (unit, end) in words_at(line, pos, &ENGLISH) {
+        for pos in separators(line, end) {
+            let Some(end) = line[pos..]
+                .starts_with("hundred")
+                .then_some(pos + "hundred".len())
+            else {
+                continue;
+            };
+            let hundreds = unit * 100;
+            numerals.push((hundreds, end));
+
+            for pos in separators(line, end) {
+                let after_and = line[pos..]
+                    .starts_with("and")
+                    .then(|| separators(line, pos + "and".len()))
+                    .into_iter()
+                    .flatten();
+                for pos in separators(line, pos).chain(after_and) {
+                    numerals.extend(
+                        below_hundred(line, pos)
+                            .into_iter()
+                            .map(|(rest, end)| (hundreds + rest, end)),
+                    );
+                }
+            }
+        }
+    }
+
+    numerals
+}
+
+fn scaled(
+    line: &str,
+    pos: usize,
+    scales: &[(&str, CalibrationNumber)],
+) -> Vec<(CalibrationNumber, usize)> {
+    let Some(((word, scale), smaller)) = scales.split_first() else {
+        return below_thousand(line, pos);
+    };
+
+    let mut numerals = scaled(line, pos, smaller);
+
+    for (multiplier, end) in below_thousand(line, pos) {
+        for pos in separators(line, end) {
+            if !line[pos..].starts_with(word) {
+                continue;
+            }
+            let base = multiplier * scale;
+            let end = pos + word.len();
+            numerals.push((base, end));
+
+            for pos in separators(line, end) {
+                numerals.extend(
+                    scaled(line, pos, smaller)
+                        .into_iter()
+                        .map(|(rest, end)| (base + rest, end)),
+                );
+            }
+        }
+    }
+
+    numerals
+}
+
+/// Every English numeral in `line`, including the ones nested inside longer numerals.
+///
+/// Numerals are built from `zero`, the units, teens and tens, `hundred`, `thousand` and
+/// `million`, with the words written directly after each other or separated by a single
+/// space or hyphen (`forty-two`, `three hundred and five`, `twothousand`). Digits count
+/// as single-digit numerals.
+pub fn numerals(line: &str) -> Vec<Numeral> {
+    let mut numerals = vec![];
+
+    for (start, c) in line.char_indices() {
+        if let Some(digit) = c.to_digit(10) {
+            numerals.push(Numeral {
+                start,
+                end: start + 1,
+                value: digit,
+            });
+            continue;
+        }
+
+        let words =
+            scaled(line, start, &SCALES)
+                .into_iter()
+                .chain(words_at(line, start, &[("zero", 0)]));
+        numerals.extend(words.map(|(value, end)| Numeral { start, end, value }));
+    }
+
+    numerals.sort_by_key(|numeral| (numeral.start, numeral.end));
+    numerals.dedup_by_key(|numeral| (numeral.start, numeral.end));
+    numerals
+}
+
+/// Reads whole English numerals (see [`numerals`]) instead of single digits.
+///
+/// The first numeral is the longest one starting at the earliest position, the last
+/// numeral the longest one ending at the latest position, so `forty-two` ends in 42,
+/// while `eightwo` still ends in 2. Numbers go up to 999,999,999, which is why
+/// calibration values are written with [`calibration_value`] and summed as `u64`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnglishNumerals;
+
+impl Tokenizer for EnglishNumerals {
+    fn first_and_last(&self, line: &str) -> Option<(CalibrationNumber, CalibrationNumber)> {
+        let numerals = numerals(line);
+
+        let first = numerals
+            .iter()
+            .min_by_key(|numeral| (numeral.start, std::cmp::Reverse(numeral.end)));
+        let last = numerals
+            .iter()
+            .max_by_key(|numeral| (numeral.end, std::cmp::Reverse(numeral.start)));
+
+        first
+            .zip(last)
+            .map(|(first, last)| (first.value, last.value))
+    }
+
+    fn expected(&self) -> &'static str {
+        "at least one numeral"
+    }
+}
+
+/// Writes `last` behind `first`, which is `10 * first + last` for single digits. Two
+/// numerals can make up to 18 digits, hence the wider result.
+pub fn calibration_value(first: CalibrationNumber, last: CalibrationNumber) -> u64 {
+    let mut shift = 10;
+    while shift <= u64::from(last) {
+        shift *= 10;
+    }
+    u64::from(first) * shift + u64::from(last)
 }
 
 #[aoc(day1, part1)]
@@ -74,17 +822,37 @@ pub fn day01_part1(numbers: &[Vec<CalibrationNumber>]) -> u32 {
 }
 
 #[aoc(day1, part2)]
-pub fn day01_part_2(numbers: &[(CalibrationNumber, CalibrationNumber)]) -> u32 {
+pub fn day01_part_2(numbers: &[(CalibrationNumber, CalibrationNumber)]) -> u64 {
     numbers
         .iter()
-        .map(|(first, last)| first * 10 + last)
-        .reduce(|acc, cur| acc + cur)
-        .unwrap_or(0)
+        .map(|(first, last)| calibration_value(*first, *last))
+        .sum()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_rng::Rng;
+
+    // the original scan, which tries every pattern at every offset
+    fn parse_calibration_line_by_scan(
+        line: &str,
+        lexicon: &Lexicon,
+    ) -> Option<(CalibrationNumber, CalibrationNumber)> {
+        let mut first = None;
+        let mut last = None;
+        for i in 0..line.len() {
+            for (p, n) in lexicon.entries() {
+                if line[i..].starts_with(p.as_str()) {
+                    if first.is_none() {
+                        first = Some(*n);
+                    }
+                    last = Some(*n)
+                }
+            }
+        }
+        first.zip(last)
+    }
 
     const INPUT_PART_1: &str = "1abc2
 pqr3stu8vwx
@@ -117,8 +885,317 @@ zoneight234
     #[test]
     fn test_generator_day_1_part2() {
         assert_eq!(
-            generator_day01_part2(INPUT_PART_2),
-            vec![(2, 9), (8, 3), (1, 3), (2, 4), (4, 2), (1, 4), (7, 6)]
+            generator_day01_part2_english(INPUT_PART_2),
+            Ok(vec![(2, 9), (8, 3), (1, 3), (2, 4), (4, 2), (1, 4), (7, 6)])
         )
     }
+
+    #[test]
+    fn test_generator_day_1_part2_errors() {
+        let err = generator_day01_part2_english("two1nine\nabcdef").unwrap_err();
+        assert_eq!(err.day(), Some(1));
+        assert_eq!(err.line(), Some(2));
+        assert_eq!(err.span(), 0..6);
+    }
+
+    #[test]
+    fn test_parse_calibration_line_overlaps() {
+        let english = Lexicon::language(Language::English);
+        let parse = |line| parse_calibration_line(line, &english);
+
+        assert_eq!(parse("eightwo"), Ok((8, 2)));
+        assert_eq!(parse("oneight"), Ok((1, 8)));
+        assert_eq!(parse("twone"), Ok((2, 1)));
+        assert_eq!(parse("sevenine"), Ok((7, 9)));
+        assert_eq!(parse("xtwone3four"), Ok((2, 4)));
+    }
+
+    #[test]
+    fn test_parse_calibration_line_matches_scan() {
+        const ALPHABET: &[u8] = b"onetwhrfuivsxg123456789.";
+
+        let lexicon = Lexicon::language(Language::English);
+
+        let mut rng = Rng::new(0x2545_f491_4f6c_dd1d);
+
+        for _ in 0..2000 {
+            let line = rng
+                .vec(0, 39, |rng| *rng.pick(ALPHABET) as char)
+                .into_iter()
+                .collect::<String>();
+
+            assert_eq!(
+                parse_calibration_line(&line, &lexicon).ok(),
+                parse_calibration_line_by_scan(&line, &lexicon),
+                "{line}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_languages() {
+        let german = Lexicon::language(Language::German);
+        let french = Lexicon::language(Language::French);
+
+        assert_eq!(
+            generator_day01_part2("xeinsiebenz\nfünfachtzwei4", &german),
+            Ok(vec![(1, 7), (5, 4)])
+        );
+        assert_eq!(
+            generator_day01_part2("undeuxtrois\nhuitrois", &french),
+            Ok(vec![(1, 3), (8, 3)])
+        );
+        assert!(generator_day01_part2("eins", &french).is_err());
+        assert_eq!(
+            generator_day01_part2("one2", &Lexicon::digits()),
+            Ok(vec![(2, 2)])
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let merged =
+            Lexicon::language(Language::German).merge(&Lexicon::language(Language::French));
+
+        assert_eq!(
+            generator_day01_part2("einsdeux\nneufsechs", &merged),
+            Ok(vec![(1, 2), (9, 6)])
+        );
+        // digits appear in both lexicons, but only once in the merge
+        assert_eq!(merged.entries().len(), 27);
+
+        let custom = "un 7".parse::<Lexicon>().unwrap();
+        assert_eq!(
+            generator_day01_part2("un", &merged.merge(&custom)),
+            Ok(vec![(7, 7)])
+        );
+    }
+
+    #[test]
+    fn test_lexicon_parse() {
+        let lexicon = "# dutch\neen 1\n\n  twee 2\ndrie 3"
+            .parse::<Lexicon>()
+            .unwrap();
+        assert_eq!(
+            lexicon.entries(),
+            &[
+                ("een".to_string(), 1),
+                ("twee".to_string(), 2),
+                ("drie".to_string(), 3)
+            ]
+        );
+
+        let err = "een 1\ntwee 12".parse::<Lexicon>().unwrap_err();
+        assert_eq!(err.line(), Some(2));
+        assert_eq!(err.span(), 5..7);
+
+        let err = "een".parse::<Lexicon>().unwrap_err();
+        assert_eq!(err.expected(), "a digit after the word");
+    }
+
+    #[test]
+    fn test_lexicon_from_file() {
+        let path =
+            std::env::temp_dir().join(format!("aoc-2023-lexicon-{}.txt", std::process::id()));
+        fs::write(&path, "uno 1\ndos 2\n").unwrap();
+
+        let lexicon = Lexicon::from_file(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            generator_day01_part2("xunodosy", &lexicon.unwrap()),
+            Ok(vec![(1, 2)])
+        );
+        assert!(matches!(
+            Lexicon::from_file(&path),
+            Err(LexiconError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_numerals() {
+        let values = |line| {
+            numerals(line)
+                .into_iter()
+                .map(|Numeral { start, end, value }| (start, end, value))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(values("twelve"), vec![(0, 6, 12)]);
+        assert_eq!(values("forty-two"), vec![(0, 5, 40), (0, 9, 42), (6, 9, 2)]);
+        assert_eq!(values("x3 hundredy"), vec![(1, 2, 3)]);
+        assert!(values("three hundred").contains(&(0, 13, 300)));
+        assert!(values("nine hundred and ninety-nine").contains(&(0, 28, 999)));
+        assert!(values("twothousandfive").contains(&(0, 15, 2005)));
+        assert!(values("one million two hundred thousand").contains(&(0, 32, 1_200_000)));
+        assert!(values("seventeen").contains(&(0, 5, 7)));
+        assert!(values("seventeen").contains(&(0, 9, 17)));
+    }
+
+    #[test]
+    fn test_english_numerals() {
+        let parse = |line| EnglishNumerals.first_and_last(line);
+        assert_eq!(parse("twelve"), Some((12, 12)));
+        assert_eq!(parse("forty-two and seven"), Some((42, 7)));
+        assert_eq!(parse("xthree hundredy"), Some((300, 300)));
+        assert_eq!(parse("eightwo"), Some((8, 2)));
+        assert_eq!(parse("seventeenine"), Some((17, 9)));
+        assert_eq!(parse("1nineteen"), Some((1, 19)));
+        assert_eq!(parse("hundred"), None);
+    }
+
+    #[test]
+    fn test_generator_day01_part2_numerals() {
+        let gen =
+            generator_day01_part2("ab twenty-one cd\nninetyeight\n4x", &EnglishNumerals).unwrap();
+        assert_eq!(gen, vec![(21, 21), (98, 98), (4, 4)]);
+        assert_eq!(day01_part_2(&gen), 2121 + 9898 + 44);
+
+        // the example only differs in its last line, where `sixteen` is read as a whole
+        let gen = generator_day01_part2(INPUT_PART_2, &EnglishNumerals).unwrap();
+        assert_eq!(
+            gen[..6],
+            generator_day01_part2_english(INPUT_PART_2).unwrap()[..6]
+        );
+        assert_eq!(gen[6], (7, 16));
+        assert_eq!(day01_part_2(&gen), 281 - 76 + 716);
+
+        let gen = generator_day01_part2("nine hundred million\nfive", &EnglishNumerals).unwrap();
+        assert_eq!(day01_part_2(&gen), 900_000_000_900_000_000 + 55);
+
+        let err = generator_day01_part2("two\nhundred", &EnglishNumerals).unwrap_err();
+        assert_eq!(err.line(), Some(2));
+        assert_eq!(err.expected(), "at least one numeral");
+    }
+
+    #[test]
+    fn test_calibration_value() {
+        assert_eq!(calibration_value(4, 2), 42);
+        assert_eq!(calibration_value(42, 7), 427);
+        assert_eq!(calibration_value(3, 10), 310);
+        assert_eq!(calibration_value(0, 0), 0);
+    }
+
+    #[test]
+    fn test_trace() {
+        let lexicon = Lexicon::language(Language::English);
+        let trace = Trace::new("xtwone3four", &lexicon);
+
+        let tokens = trace
+            .tokens()
+            .iter()
+            .map(|token| (token.start, token.end, trace.word(token)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec![(1, 4, "two"), (3, 6, "one"), (6, 7, "3"), (7, 11, "four")]
+        );
+        assert_eq!(trace.first().map(|token| token.start), Some(1));
+        assert_eq!(trace.last().map(|token| token.start), Some(7));
+        assert_eq!(trace.calibration(), Some((2, 4)));
+
+        assert_eq!(
+            trace.to_string(),
+            "xtwone3four
+ ^^^        two = 2 (first)
+   ^^^      one = 1
+      ^     3 = 3
+       ^^^^ four = 4 (last)"
+        );
+
+        let empty = Trace::new("abc", &lexicon);
+        assert_eq!(empty.calibration(), None);
+        assert_eq!(empty.to_string(), "abc");
+    }
+
+    #[test]
+    fn test_trace_json() {
+        let lexicon = Lexicon::digits();
+        let traces = explain("a\"7\nx", &lexicon);
+
+        assert_eq!(
+            explain_json(&traces),
+            r#"[{"line":"a\"7","tokens":[{"start":2,"end":3,"entry":6,"word":"7","value":7,"first":true,"last":true}]},{"line":"x","tokens":[]}]"#
+        );
+    }
+
+    #[test]
+    fn test_trace_multibyte() {
+        let lexicon = Lexicon::language(Language::German);
+        let trace = Trace::new("fünf", &lexicon);
+
+        assert_eq!(trace.tokens()[0].end, 5);
+        assert_eq!(trace.to_string(), "fünf\n^^^^ fünf = 5 (first, last)");
+    }
+
+    /// Feeds `input` to a reader in chunks of `size` bytes.
+    fn sum_in_chunks(input: &[u8], size: usize, lexicon: &Lexicon) -> Result<u64, StreamError> {
+        let mut calibration = CalibrationReader::new(lexicon);
+        for chunk in input.chunks(size) {
+            calibration.feed(chunk)?;
+        }
+        calibration.finish()
+    }
+
+    #[test]
+    fn test_sum_calibration() {
+        let english = Lexicon::language(Language::English);
+
+        assert_eq!(
+            sum_calibration(INPUT_PART_2.as_bytes(), &english).unwrap(),
+            281
+        );
+        assert_eq!(
+            sum_calibration_digits(INPUT_PART_1.as_bytes()).unwrap(),
+            142
+        );
+        // a trailing newline does not start another line
+        assert_eq!(sum_calibration("1\n".as_bytes(), &english).unwrap(), 11);
+        assert_eq!(sum_calibration_digits("abc\n7".as_bytes()).unwrap(), 77);
+    }
+
+    #[test]
+    fn test_sum_calibration_small_buffers() {
+        // words, multi-byte characters and line ends split at every possible place
+        let input = "xtwone3four\nfünfzig1\nzweiundeins";
+        let lexicon =
+            Lexicon::language(Language::English).merge(&Lexicon::language(Language::German));
+        let expected = day01_part_2(&generator_day01_part2(input, &lexicon).unwrap());
+
+        for size in 1..=input.len() {
+            assert_eq!(
+                sum_in_chunks(input.as_bytes(), size, &lexicon).unwrap(),
+                expected
+            );
+            let reader = io::BufReader::with_capacity(size, input.as_bytes());
+            assert_eq!(sum_calibration(reader, &lexicon).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_sum_calibration_errors() {
+        let english = Lexicon::language(Language::English);
+
+        assert!(matches!(
+            sum_calibration("one\n\ntwo".as_bytes(), &english),
+            Err(StreamError::NoDigit { line: 2 })
+        ));
+        assert!(matches!(
+            sum_calibration(&b"one\nt\xffwo"[..], &english),
+            Err(StreamError::InvalidUtf8 { line: 2, column: 1 })
+        ));
+        // a sequence cut off by the end of the input, an overlong encoding and a surrogate
+        assert!(matches!(
+            sum_in_chunks("fü".as_bytes().split_last().unwrap().1, 1, &english),
+            Err(StreamError::InvalidUtf8 { line: 1, column: 2 })
+        ));
+        assert!(matches!(
+            sum_in_chunks(&[b'1', 0xc0, 0xaf], 1, &english),
+            Err(StreamError::InvalidUtf8 { line: 1, column: 1 })
+        ));
+        assert!(matches!(
+            sum_in_chunks(&[0xed, 0xa0, 0x80], 2, &english),
+            Err(StreamError::InvalidUtf8 { line: 1, column: 1 })
+        ));
+    }
 }
@@ -1,127 +1,308 @@
-use std::str::FromStr;
+use std::{collections::HashMap, fmt::Display, str::FromStr};
 
 use aoc_runner_derive::{aoc, aoc_generator};
 
-const RED: u32 = 12;
-const GREEN: u32 = 13;
-const BLUE: u32 = 14;
+use crate::parse_error::{parse_lines, ParseError};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Color {
-    Red,
-    Green,
-    Blue,
+/// The name of a cube colour, in lowercase.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Color(String);
+
+impl Color {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
 }
 
 impl FromStr for Color {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "red" => Ok(Self::Red),
-            "green" => Ok(Self::Green),
-            "blue" => Ok(Self::Blue),
-            _ => Err(()),
+        if s.is_empty() || !s.chars().all(char::is_alphabetic) {
+            return Err(ParseError::at(s, s, "a colour"));
         }
+        Ok(Color(s.to_lowercase()))
+    }
+}
+
+impl Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct Information {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Information {
     color: Color,
     amount: u32,
 }
 
+impl Information {
+    pub fn color(&self) -> &Color {
+        &self.color
+    }
+
+    pub fn amount(&self) -> u32 {
+        self.amount
+    }
+}
+
 impl FromStr for Information {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts = s.trim().split(' ').collect::<Vec<_>>();
+        let mut parts = s.trim().split(' ');
+
+        let amount = match parts.next() {
+            Some(amount) if !amount.is_empty() => amount
+                .parse::<u32>()
+                .map_err(|_| ParseError::at(s, amount, "a number of cubes"))?,
+            _ => return Err(ParseError::at(s, s, "a number of cubes")),
+        };
+
+        let Some(color) = parts.next() else {
+            return Err(ParseError::at_end(s, "a colour"));
+        };
+        let color = color.parse::<Color>().map_err(|err| err.within(s, color))?;
+
+        if let Some(rest) = parts.next() {
+            return Err(ParseError::at(s, rest, "`,` or `;` between draws"));
+        }
 
-        let amount = parts[0].parse::<u32>().expect("invalid input");
-        let color = parts[1].parse::<Color>().expect("invalid input");
         Ok(Information { color, amount })
     }
 }
 
+impl Display for Information {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.amount, self.color)
+    }
+}
+
+/// The cubes shown together in one round of a game.
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct Game {
-    id: u32,
+pub struct Round {
     information: Vec<Information>,
 }
 
+impl Round {
+    pub fn information(&self) -> &[Information] {
+        &self.information
+    }
+
+    /// How many cubes of `color` were shown in this round, adding up repeated draws of
+    /// the colour.
+    pub fn count(&self, color: &Color) -> u32 {
+        self.information
+            .iter()
+            .filter(|information| information.color == *color)
+            .map(Information::amount)
+            .sum()
+    }
+
+    /// How many cubes were shown in this round, of all colours together.
+    pub fn cubes(&self) -> u32 {
+        self.information.iter().map(Information::amount).sum()
+    }
+
+    /// Whether this round could have been drawn from `bag`, checking every draw on its own.
+    pub fn fits(&self, bag: &Bag) -> bool {
+        self.information
+            .iter()
+            .all(|Information { amount, color }| *amount <= bag.count(color))
+    }
+}
+
+impl Display for Round {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, information) in self.information.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{information}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    id: u32,
+    rounds: Vec<Round>,
+}
+
+impl Game {
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn rounds(&self) -> &[Round] {
+        &self.rounds
+    }
+
+    /// Every draw of every round, in order.
+    pub fn information(&self) -> impl Iterator<Item = &Information> {
+        self.rounds.iter().flat_map(Round::information)
+    }
+
+    /// The most cubes shown at once in a single round.
+    pub fn max_cubes_shown(&self) -> u32 {
+        self.rounds.iter().map(Round::cubes).max().unwrap_or(0)
+    }
+
+    /// The (0-based) indices of all rounds that could not have been drawn from `bag`.
+    pub fn violating_rounds<'a>(&'a self, bag: &'a Bag) -> impl Iterator<Item = usize> + 'a {
+        self.rounds
+            .iter()
+            .enumerate()
+            .filter(|(_, round)| !round.fits(bag))
+            .map(|(index, _)| index)
+    }
+
+    pub fn first_violating_round(&self, bag: &Bag) -> Option<usize> {
+        self.violating_rounds(bag).next()
+    }
+
+    /// The smallest bag that could have produced every round of this game.
+    pub fn minimal_bag(&self) -> Bag {
+        let mut cubes = HashMap::new();
+        for round in &self.rounds {
+            for Information { amount, color } in &round.information {
+                let count = cubes.entry(color.clone()).or_insert(0);
+                *count = (*count).max(*amount);
+            }
+        }
+        Bag { cubes }
+    }
+}
+
 impl FromStr for Game {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut segments = s.split(':').collect::<Vec<_>>();
+        let Some((header, draws)) = s.split_once(':') else {
+            return Err(ParseError::at_end(s, "`:` after the game id"));
+        };
 
-        let id = segments.remove(0).split(' ').collect::<Vec<_>>()[1]
+        let Some(id) = header.strip_prefix("Game ") else {
+            return Err(ParseError::at(s, header, "`Game <id>`"));
+        };
+        let id = id
             .parse::<u32>()
-            .expect("invalid input");
+            .map_err(|_| ParseError::at(s, id, "a game id"))?;
 
-        let mut information = vec![];
+        let mut rounds = vec![];
+
+        for round in draws.split("; ") {
+            let mut information = vec![];
+            for draw in round.split(", ") {
+                information.push(
+                    draw.parse()
+                        .map_err(|err: ParseError| err.within(s, draw))?,
+                );
+            }
+            rounds.push(Round { information });
+        }
 
-        segments[0].split("; ").for_each(|elem| {
-            elem.split(", ")
-                .for_each(|game| information.push(game.parse().expect("invalid input")))
-        });
+        Ok(Game { id, rounds })
+    }
+}
 
-        Ok(Game { id, information })
+impl Display for Game {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Game {}: ", self.id)?;
+        for (index, round) in self.rounds.iter().enumerate() {
+            if index > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{round}")?;
+        }
+        Ok(())
     }
 }
 
-#[aoc_generator(day2)]
-fn generator_day02_part1(inp: &str) -> Vec<Game> {
-    inp.lines()
-        .map(|line| line.parse::<Game>().expect("invalid input"))
-        .collect::<Vec<_>>()
+/// How many cubes of each colour are in the bag. Colours that are not listed have no cubes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bag {
+    cubes: HashMap<Color, u32>,
 }
 
-#[aoc(day2, part1)]
-fn day02_part1(games: &[Game]) -> u32 {
+impl Bag {
+    /// The bag from the puzzle: 12 red, 13 green and 14 blue cubes.
+    pub fn puzzle() -> Self {
+        [("red", 12), ("green", 13), ("blue", 14)]
+            .into_iter()
+            .map(|(color, count)| (Color(color.to_string()), count))
+            .collect()
+    }
+
+    pub fn count(&self, color: &Color) -> u32 {
+        self.cubes.get(color).copied().unwrap_or(0)
+    }
+
+    pub fn colors(&self) -> impl Iterator<Item = &Color> {
+        self.cubes.keys()
+    }
+
+    /// Whether every round of `game` could have been drawn from this bag.
+    pub fn allows(&self, game: &Game) -> bool {
+        game.rounds.iter().all(|round| round.fits(self))
+    }
+}
+
+impl FromIterator<(Color, u32)> for Bag {
+    fn from_iter<T: IntoIterator<Item = (Color, u32)>>(iter: T) -> Self {
+        Bag {
+            cubes: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// Sums the ids of all games that are possible with `bag`.
+pub fn possible_games(games: &[Game], bag: &Bag) -> u32 {
     games
         .iter()
-        .filter(|Game { information, .. }| {
-            information.iter().all(|Information { color, amount }| {
-                *amount
-                    <= match color {
-                        Color::Red => RED,
-                        Color::Green => GREEN,
-                        Color::Blue => BLUE,
-                    }
-            })
-        })
-        .map(|Game { id, .. }| *id)
+        .filter(|game| bag.allows(game))
+        .map(Game::id)
         .sum()
 }
 
-#[aoc(day2, part2)]
-fn day02_part2(games: &[Game]) -> u32 {
+/// Sums the power of the minimal bag of every game, taken over `colors`.
+/// A colour that a game never shows makes the power of that game 0.
+pub fn sum_of_powers(games: &[Game], colors: &[Color]) -> u32 {
     games
         .iter()
-        .map(|Game { information, .. }| {
-            let mut red = 0;
-            let mut green = 0;
-            let mut blue = 0;
-
-            for Information { color, amount } in information {
-                match color {
-                    Color::Red => red = red.max(*amount),
-                    Color::Green => green = green.max(*amount),
-                    Color::Blue => blue = blue.max(*amount),
-                }
-            }
-
-            red * green * blue
+        .map(|game| {
+            let minimal = game.minimal_bag();
+            colors
+                .iter()
+                .map(|color| minimal.count(color))
+                .product::<u32>()
         })
         .sum()
 }
 
+#[aoc_generator(day2)]
+fn generator_day02_part1(inp: &str) -> Result<Vec<Game>, ParseError> {
+    parse_lines(2, inp, str::parse)
+}
+
+#[aoc(day2, part1)]
+fn day02_part1(games: &[Game]) -> u32 {
+    possible_games(games, &Bag::puzzle())
+}
+
+#[aoc(day2, part2)]
+fn day02_part2(games: &[Game]) -> u32 {
+    let colors = Bag::puzzle().colors().cloned().collect::<Vec<_>>();
+    sum_of_powers(games, &colors)
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use crate::test_rng::Rng;
 
     const INPUT: &str = "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
 Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
@@ -129,190 +310,188 @@ Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
 Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
 Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
 
+    fn color(name: &str) -> Color {
+        name.parse().unwrap()
+    }
+
+    fn info(amount: u32, name: &str) -> Information {
+        Information {
+            color: color(name),
+            amount,
+        }
+    }
+
+    fn bag(cubes: &[(&str, u32)]) -> Bag {
+        cubes
+            .iter()
+            .map(|(name, count)| (color(name), *count))
+            .collect()
+    }
+
+    fn round(draws: &[(u32, &str)]) -> Round {
+        Round {
+            information: draws
+                .iter()
+                .map(|(amount, name)| info(*amount, name))
+                .collect(),
+        }
+    }
+
     #[test]
     fn test_generator_part_1() {
         let result = vec![
             Game {
                 id: 1,
-                information: vec![
-                    Information {
-                        amount: 3,
-                        color: Color::Blue,
-                    },
-                    Information {
-                        amount: 4,
-                        color: Color::Red,
-                    },
-                    Information {
-                        amount: 1,
-                        color: Color::Red,
-                    },
-                    Information {
-                        amount: 2,
-                        color: Color::Green,
-                    },
-                    Information {
-                        amount: 6,
-                        color: Color::Blue,
-                    },
-                    Information {
-                        amount: 2,
-                        color: Color::Green,
-                    },
+                rounds: vec![
+                    round(&[(3, "blue"), (4, "red")]),
+                    round(&[(1, "red"), (2, "green"), (6, "blue")]),
+                    round(&[(2, "green")]),
                 ],
             },
             Game {
                 id: 2,
-                information: vec![
-                    Information {
-                        amount: 1,
-                        color: Color::Blue,
-                    },
-                    Information {
-                        amount: 2,
-                        color: Color::Green,
-                    },
-                    Information {
-                        amount: 3,
-                        color: Color::Green,
-                    },
-                    Information {
-                        amount: 4,
-                        color: Color::Blue,
-                    },
-                    Information {
-                        amount: 1,
-                        color: Color::Red,
-                    },
-                    Information {
-                        amount: 1,
-                        color: Color::Green,
-                    },
-                    Information {
-                        amount: 1,
-                        color: Color::Blue,
-                    },
+                rounds: vec![
+                    round(&[(1, "blue"), (2, "green")]),
+                    round(&[(3, "green"), (4, "blue"), (1, "red")]),
+                    round(&[(1, "green"), (1, "blue")]),
                 ],
             },
             Game {
                 id: 3,
-                information: vec![
-                    Information {
-                        amount: 8,
-                        color: Color::Green,
-                    },
-                    Information {
-                        amount: 6,
-                        color: Color::Blue,
-                    },
-                    Information {
-                        amount: 20,
-                        color: Color::Red,
-                    },
-                    Information {
-                        amount: 5,
-                        color: Color::Blue,
-                    },
-                    Information {
-                        amount: 4,
-                        color: Color::Red,
-                    },
-                    Information {
-                        amount: 13,
-                        color: Color::Green,
-                    },
-                    Information {
-                        amount: 5,
-                        color: Color::Green,
-                    },
-                    Information {
-                        amount: 1,
-                        color: Color::Red,
-                    },
+                rounds: vec![
+                    round(&[(8, "green"), (6, "blue"), (20, "red")]),
+                    round(&[(5, "blue"), (4, "red"), (13, "green")]),
+                    round(&[(5, "green"), (1, "red")]),
                 ],
             },
             Game {
                 id: 4,
-                information: vec![
-                    Information {
-                        amount: 1,
-                        color: Color::Green,
-                    },
-                    Information {
-                        amount: 3,
-                        color: Color::Red,
-                    },
-                    Information {
-                        amount: 6,
-                        color: Color::Blue,
-                    },
-                    Information {
-                        amount: 3,
-                        color: Color::Green,
-                    },
-                    Information {
-                        amount: 6,
-                        color: Color::Red,
-                    },
-                    Information {
-                        amount: 3,
-                        color: Color::Green,
-                    },
-                    Information {
-                        amount: 15,
-                        color: Color::Blue,
-                    },
-                    Information {
-                        amount: 14,
-                        color: Color::Red,
-                    },
+                rounds: vec![
+                    round(&[(1, "green"), (3, "red"), (6, "blue")]),
+                    round(&[(3, "green"), (6, "red")]),
+                    round(&[(3, "green"), (15, "blue"), (14, "red")]),
                 ],
             },
             Game {
                 id: 5,
-                information: vec![
-                    Information {
-                        amount: 6,
-                        color: Color::Red,
-                    },
-                    Information {
-                        amount: 1,
-                        color: Color::Blue,
-                    },
-                    Information {
-                        amount: 3,
-                        color: Color::Green,
-                    },
-                    Information {
-                        amount: 2,
-                        color: Color::Blue,
-                    },
-                    Information {
-                        amount: 1,
-                        color: Color::Red,
-                    },
-                    Information {
-                        amount: 2,
-                        color: Color::Green,
-                    },
+                rounds: vec![
+                    round(&[(6, "red"), (1, "blue"), (3, "green")]),
+                    round(&[(2, "blue"), (1, "red"), (2, "green")]),
                 ],
             },
         ];
 
-        assert_eq!(generator_day02_part1(INPUT), result);
+        assert_eq!(generator_day02_part1(INPUT), Ok(result));
+    }
+
+    #[test]
+    fn test_round_queries() {
+        let games = generator_day02_part1(INPUT).unwrap();
+        let puzzle = Bag::puzzle();
+
+        assert_eq!(
+            games.iter().map(Game::max_cubes_shown).collect::<Vec<_>>(),
+            vec![9, 8, 34, 32, 10]
+        );
+        assert_eq!(
+            games[2].violating_rounds(&puzzle).collect::<Vec<_>>(),
+            vec![0]
+        );
+        assert_eq!(games[3].first_violating_round(&puzzle), Some(2));
+        assert_eq!(games[0].first_violating_round(&puzzle), None);
+        assert_eq!(games[1].information().count(), 7);
+
+        // a colour drawn twice in a round is counted in total, but bags are checked
+        // against every draw on its own
+        let game = Game::from_str("Game 1: 2 red, 3 red; 4 red").unwrap();
+        assert_eq!(game.rounds()[0].count(&color("red")), 5);
+        assert_eq!(game.minimal_bag(), bag(&[("red", 4)]));
+        assert_eq!(game.first_violating_round(&bag(&[("red", 4)])), None);
+        assert_eq!(
+            game.violating_rounds(&bag(&[("red", 3)]))
+                .collect::<Vec<_>>(),
+            vec![1]
+        );
     }
 
     #[test]
     fn test_day02_part_1() {
-        let gen = generator_day02_part1(INPUT);
+        let gen = generator_day02_part1(INPUT).unwrap();
 
         assert_eq!(day02_part1(&gen), 8);
     }
 
     #[test]
     fn test_day02_part_2() {
-        let gen = generator_day02_part1(INPUT);
+        let gen = generator_day02_part1(INPUT).unwrap();
 
         assert_eq!(day02_part2(&gen), 2286);
     }
+
+    #[test]
+    fn test_other_colours() {
+        let games = generator_day02_part1(
+            "Game 1: 4 yellow, 1 Red; 2 yellow
+Game 2: 7 yellow; 2 purple
+Game 3: 1 red",
+        )
+        .unwrap();
+
+        assert_eq!(
+            games[0].rounds()[0].information()[1].color().as_str(),
+            "red"
+        );
+        assert_eq!(games[1].minimal_bag(), bag(&[("yellow", 7), ("purple", 2)]));
+
+        let yellow_and_red = bag(&[("yellow", 5), ("red", 1)]);
+        assert_eq!(possible_games(&games, &yellow_and_red), 1 + 3);
+        assert_eq!(possible_games(&games, &bag(&[("red", 9)])), 3);
+        assert_eq!(possible_games(&games, &Bag::default()), 0);
+
+        assert_eq!(sum_of_powers(&games, &[color("yellow"), color("red")]), 4);
+        assert_eq!(sum_of_powers(&games, &[color("yellow")]), 4 + 7);
+        assert_eq!(sum_of_powers(&games, &[]), 3);
+    }
+
+    #[test]
+    fn test_generator_errors() {
+        let err = generator_day02_part1("Game 1: 3 blue, 4 gr3en; 1 red").unwrap_err();
+        assert_eq!(err.line(), Some(1));
+        assert_eq!(err.span(), 18..23);
+        assert_eq!(err.expected(), "a colour");
+
+        let err = generator_day02_part1("Game 1: 3 blue\nGame 2: x red").unwrap_err();
+        assert_eq!(err.line(), Some(2));
+        assert_eq!(err.span(), 8..9);
+
+        let err = Game::from_str("Game 1: 3 blue; 4").unwrap_err();
+        assert_eq!(err.span(), 17..17);
+        assert_eq!(err.expected(), "a colour");
+
+        let err = Game::from_str("Round 1: 3 blue").unwrap_err();
+        assert_eq!(err.span(), 0..7);
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let mut rng = Rng::new(2);
+        let colors: &[&str] = &["red", "green", "blue", "yellow", "türkis"];
+
+        for _ in 0..200 {
+            let game = Game {
+                id: rng.below(1000) as u32,
+                rounds: rng.vec(1, 4, |rng| Round {
+                    information: rng.vec(1, 4, |rng| {
+                        let color: &&str = rng.pick(colors);
+                        info(rng.below(30) as u32, color)
+                    }),
+                }),
+            };
+
+            assert_eq!(Game::from_str(&game.to_string()), Ok(game));
+        }
+
+        let input = INPUT.lines().next().unwrap();
+        assert_eq!(Game::from_str(input).unwrap().to_string(), input);
+    }
 }
@@ -1,94 +1,132 @@
-use std::collections::HashSet;
+use std::collections::HashMap;
 
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use crate::{grid::Grid, parse_error::ParseError};
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Number {
     num: u32,
     neighbours: Vec<(char, usize, usize)>,
 }
 
-fn get_neighbours(lines: &Vec<&str>, col: usize, row: usize) -> Vec<(char, usize, usize)> {
-    let mut neighbours = vec![];
+impl Number {
+    pub fn value(&self) -> u32 {
+        self.num
+    }
 
-    let lower_bound = row.max(1) - 1;
+    /// The symbols around this number as `(symbol, x, y)`, in reading order.
+    pub fn neighbours(&self) -> &[(char, usize, usize)] {
+        &self.neighbours
+    }
+}
 
-    lines
-        .iter()
-        .take((row + 2).min(lines.len()))
-        .skip(lower_bound)
-        .enumerate()
-        .for_each(|(y, line)| {
-            if col >= line.len() {
-                return;
-            }
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    symbol: char,
+    numbers: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schematic {
+    numbers: Vec<Number>,
+    symbols: HashMap<(usize, usize), Symbol>,
+}
 
-            let cs = line.chars().collect::<Vec<_>>();
-            let c = cs[col];
+impl Schematic {
+    pub fn numbers(&self) -> &[Number] {
+        &self.numbers
+    }
 
-            if c.is_numeric() || c == '.' {
-                return;
-            }
+    /// Every symbol as `(symbol, x, y)`, in no particular order.
+    pub fn symbols(&self) -> impl Iterator<Item = (char, usize, usize)> + '_ {
+        self.symbols
+            .iter()
+            .map(|((x, y), Symbol { symbol, .. })| (*symbol, *x, *y))
+    }
+
+    /// All numbers adjacent to the symbol at `(x, y)`. Empty if there is no symbol.
+    pub fn numbers_adjacent_to(&self, x: usize, y: usize) -> impl Iterator<Item = &Number> {
+        self.symbols
+            .get(&(x, y))
+            .map(|Symbol { numbers, .. }| numbers.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .map(|index| &self.numbers[*index])
+    }
 
-            neighbours.push((c, col, lower_bound + y))
-        });
+    /// All symbols adjacent to the `index`-th number of the schematic, in reading order.
+    pub fn symbols_adjacent_to(&self, index: usize) -> &[(char, usize, usize)] {
+        self.numbers
+            .get(index)
+            .map(Number::neighbours)
+            .unwrap_or_default()
+    }
+}
 
-    neighbours
+fn is_symbol(c: char) -> bool {
+    !c.is_ascii_digit() && c != '.'
 }
 
 #[aoc_generator(day03)]
-fn generator_day03_part1(inp: &str) -> Vec<Number> {
+fn generator_day03_part1(inp: &str) -> Result<Schematic, ParseError> {
+    let grid = inp.parse::<Grid<char>>().map_err(|err| err.in_day(3))?;
+
     let mut numbers = vec![];
-    let lines = inp.lines().collect::<Vec<_>>();
-
-    let mut num: Option<u32> = None;
-    let mut neighbours = vec![];
-
-    for (row, line) in lines.iter().enumerate() {
-        for (col, c) in line.chars().enumerate() {
-            if c.is_numeric() {
-                let digit = c.to_digit(10).unwrap();
-                if num.is_none() && col > 0 {
-                    let mut new_neighbours = get_neighbours(&lines, col - 1, row);
-                    neighbours.append(&mut new_neighbours);
-                }
+    let mut symbols = grid
+        .iter()
+        .filter(|(_, _, c)| is_symbol(**c))
+        .map(|(x, y, c)| {
+            let symbol = Symbol {
+                symbol: *c,
+                numbers: vec![],
+            };
+            ((x, y), symbol)
+        })
+        .collect::<HashMap<_, _>>();
 
-                num = num.map(|num| num * 10 + digit).or(Some(digit));
-                let mut new_neighbours = get_neighbours(&lines, col, row);
-                neighbours.append(&mut new_neighbours);
-                continue;
-            } else if let Some(n) = num {
-                let mut new_neighbours = get_neighbours(&lines, col, row);
-                neighbours.append(&mut new_neighbours);
-
-                numbers.push(Number {
-                    num: n,
-                    neighbours: neighbours.clone(),
-                });
-                neighbours = vec![];
-                num = None;
+    let mut push_number = |num: u32, mut neighbours: Vec<(char, usize, usize)>| {
+        // the digits find their neighbours column by column, not row by row
+        neighbours.sort_by_key(|(_, x, y)| (*y, *x));
+        for (_, x, y) in &neighbours {
+            if let Some(symbol) = symbols.get_mut(&(*x, *y)) {
+                symbol.numbers.push(numbers.len());
             }
         }
+        numbers.push(Number { num, neighbours });
+    };
+
+    for (y, row) in grid.rows().enumerate() {
+        let mut num: Option<u32> = None;
+        let mut neighbours = vec![];
 
-        if let Some(n) = num {
-            let mut new_neighbours = get_neighbours(&lines, line.len() - 1, row);
-            neighbours.append(&mut new_neighbours);
+        for (x, c) in row.iter().enumerate() {
+            if let Some(digit) = c.to_digit(10) {
+                num = Some(num.unwrap_or(0) * 10 + digit);
+
+                for (nx, ny, neighbour) in grid.neighbours8(x, y) {
+                    let neighbour = (*neighbour, nx, ny);
+                    if is_symbol(neighbour.0) && !neighbours.contains(&neighbour) {
+                        neighbours.push(neighbour);
+                    }
+                }
+            } else if let Some(num) = num.take() {
+                push_number(num, std::mem::take(&mut neighbours));
+            }
+        }
 
-            numbers.push(Number {
-                num: n,
-                neighbours: neighbours.clone(),
-            });
-            neighbours = vec![];
-            num = None;
+        if let Some(num) = num {
+            push_number(num, neighbours);
         }
     }
 
-    numbers
+    Ok(Schematic { numbers, symbols })
 }
 
 #[aoc(day03, part1)]
-pub fn day03_part1(numbers: &[Number]) -> u32 {
-    numbers
+pub fn day03_part1(schematic: &Schematic) -> u32 {
+    schematic
+        .numbers
         .iter()
         .filter(|Number { neighbours, .. }| !neighbours.is_empty())
         .map(|Number { num, .. }| *num)
@@ -96,25 +134,17 @@ pub fn day03_part1(numbers: &[Number]) -> u32 {
 }
 
 #[aoc(day03, part2)]
-pub fn day03_part2(numbers: &[Number]) -> u32 {
-    let set = numbers
-        .iter()
-        .flat_map(|Number { neighbours, .. }| neighbours)
-        .cloned()
-        .collect::<HashSet<_>>();
-
-    set.into_iter()
-        .map(|symbol| {
-            let nums = numbers
+pub fn day03_part2(schematic: &Schematic) -> u32 {
+    schematic
+        .symbols
+        .values()
+        .filter(|Symbol { numbers, .. }| numbers.len() == 2)
+        .map(|Symbol { numbers, .. }| {
+            numbers
                 .iter()
-                .filter(|Number { neighbours, .. }| neighbours.contains(&symbol))
-                .map(|Number { num, .. }| *num)
-                .collect::<Vec<_>>();
-
-            nums
+                .map(|index| schematic.numbers[*index].num)
+                .product::<u32>()
         })
-        .filter(|nums| nums.len() == 2)
-        .map(|nums| nums.into_iter().reduce(|memo, cur| memo * cur).unwrap_or(0))
         .sum()
 }
 
@@ -178,20 +208,82 @@ mod tests {
                 neighbours: vec![('*', 5, 8)],
             },
         ];
-        assert_eq!(expected, generator_day03_part1(INPUT));
+        assert_eq!(expected, generator_day03_part1(INPUT).unwrap().numbers());
     }
 
     #[test]
     fn test_day03_part1() {
-        let gen = generator_day03_part1(INPUT);
+        let gen = generator_day03_part1(INPUT).unwrap();
 
         assert_eq!(day03_part1(&gen), 4361);
     }
 
     #[test]
     fn test_day03_part2() {
-        let gen = generator_day03_part1(INPUT);
+        let gen = generator_day03_part1(INPUT).unwrap();
 
         assert_eq!(day03_part2(&gen), 467835);
     }
+
+    #[test]
+    fn test_generator_edges() {
+        // numbers touching the border of the schematic, and a symbol shared by two digits
+        let input = "12.
+*..
+.34";
+        assert_eq!(
+            generator_day03_part1(input).unwrap().numbers(),
+            &[
+                Number {
+                    num: 12,
+                    neighbours: vec![('*', 0, 1)],
+                },
+                Number {
+                    num: 34,
+                    neighbours: vec![('*', 0, 1)],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_schematic_queries() {
+        let schematic = generator_day03_part1(INPUT).unwrap();
+
+        let around = |x, y| {
+            schematic
+                .numbers_adjacent_to(x, y)
+                .map(Number::value)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(around(3, 1), vec![467, 35]);
+        assert_eq!(around(5, 8), vec![755, 598]);
+        assert_eq!(around(3, 4), vec![617]);
+        assert_eq!(around(0, 0), vec![]);
+
+        assert_eq!(schematic.symbols_adjacent_to(0), &[('*', 3, 1)]);
+        assert_eq!(schematic.symbols_adjacent_to(1), &[]);
+        assert_eq!(schematic.symbols_adjacent_to(42), &[]);
+
+        let mut symbols = schematic.symbols().collect::<Vec<_>>();
+        symbols.sort_by_key(|(_, x, y)| (*y, *x));
+        assert_eq!(
+            symbols,
+            vec![
+                ('*', 3, 1),
+                ('#', 6, 3),
+                ('*', 3, 4),
+                ('+', 5, 5),
+                ('$', 3, 8),
+                ('*', 5, 8)
+            ]
+        );
+
+        // symbols on different rows come back row by row, not in the order the digits find them
+        let schematic = generator_day03_part1("...*\n.12.\n#...").unwrap();
+        assert_eq!(
+            schematic.symbols_adjacent_to(0),
+            &[('*', 3, 0), ('#', 0, 2)]
+        );
+    }
 }
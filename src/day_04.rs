@@ -1,104 +1,545 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{collections::HashSet, error::Error, fmt::Display, str::FromStr};
 
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use crate::{
+    json,
+    parse_error::{parse_lines, ParseError},
+};
+
+/// The winning numbers of a card, as a bitset while they are small enough to fit into one.
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct Card {
+enum WinningSet {
+    Small(u128),
+    Large(HashSet<u32>),
+}
+
+impl WinningSet {
+    fn new(wins: &[u32]) -> Self {
+        if wins.iter().all(|n| *n < u128::BITS) {
+            WinningSet::Small(wins.iter().fold(0, |set, n| set | 1 << n))
+        } else {
+            WinningSet::Large(wins.iter().copied().collect())
+        }
+    }
+
+    fn contains(&self, n: u32) -> bool {
+        match self {
+            WinningSet::Small(set) => n < u128::BITS && set & 1 << n != 0,
+            WinningSet::Large(set) => set.contains(&n),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Card {
     id: u32,
     wins: Vec<u32>,
     own: Vec<u32>,
+    winning: WinningSet,
+    matches: u32,
+}
+
+impl Card {
+    pub fn new(id: u32, wins: Vec<u32>, own: Vec<u32>) -> Self {
+        let winning = WinningSet::new(&wins);
+        let matches = own.iter().filter(|n| winning.contains(**n)).count() as u32;
+        Card {
+            id,
+            wins,
+            own,
+            winning,
+            matches,
+        }
+    }
+
+    /// How many of the own numbers are winning numbers. Own numbers that appear more
+    /// than once count every time.
+    pub fn matches(&self) -> u32 {
+        self.matches
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn wins(&self) -> &[u32] {
+        &self.wins
+    }
+
+    pub fn own(&self) -> &[u32] {
+        &self.own
+    }
+}
+
+fn parse_numbers(s: &str) -> Result<Vec<u32>, ParseError> {
+    s.split(' ')
+        .filter(|c| !c.trim().is_empty())
+        .map(|c| {
+            c.trim()
+                .parse::<u32>()
+                .map_err(|_| ParseError::at(s, c, "a number"))
+        })
+        .collect()
 }
 
 impl FromStr for Card {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut input = s.split(':');
+        let Some((card_info, game)) = s.split_once(':') else {
+            return Err(ParseError::at_end(s, "`:` after the card id"));
+        };
 
-        let card_info = input.next().expect("invalid input");
-        let card_id = card_info
-            .trim_start_matches("Card ")
+        let Some(card_id) = card_info.strip_prefix("Card ") else {
+            return Err(ParseError::at(s, card_info, "`Card <id>`"));
+        };
+        let card_id = card_id
             .trim()
             .parse::<u32>()
-            .expect("invalid input");
-
-        let mut game = input.next().expect("invalid input").split(" | ");
-        let wins = game
-            .next()
-            .expect("invalid input")
-            .split(' ')
-            .filter(|c| !c.trim().is_empty())
-            .map(|c| c.trim().parse::<u32>().expect("invalid input"))
-            .collect::<Vec<_>>();
-        let own = game
-            .next()
-            .expect("invalid input")
-            .split(' ')
-            .filter(|c| !c.trim().is_empty())
-            .map(|c| c.trim().parse::<u32>().expect("invalid input"))
-            .collect::<Vec<_>>();
+            .map_err(|_| ParseError::at(s, card_id, "a card id"))?;
 
-        Ok(Card {
-            id: card_id,
-            wins,
-            own,
-        })
+        let Some((wins, own)) = game.split_once(" | ") else {
+            return Err(ParseError::at(
+                s,
+                game,
+                "winning and own numbers separated by ` | `",
+            ));
+        };
+        let wins = parse_numbers(wins).map_err(|err| err.within(s, wins))?;
+        let own = parse_numbers(own).map_err(|err| err.within(s, own))?;
+
+        Ok(Card::new(card_id, wins, own))
+    }
+}
+
+impl Display for Card {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let join = |numbers: &[u32]| {
+            numbers
+                .iter()
+                .map(u32::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        write!(
+            f,
+            "Card {}: {} | {}",
+            self.id,
+            join(&self.wins),
+            join(&self.own)
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableError {
+    /// The cards at the (0-based) positions `first` and `second` share the id `id`.
+    Duplicate {
+        id: u32,
+        first: usize,
+        second: usize,
+    },
+    /// There is no card `id`, although the card at position `next` has a higher id.
+    Missing { id: u32, next: usize },
+}
+
+impl Display for TableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TableError::Duplicate { id, first, second } => write!(
+                f,
+                "card {id} appears twice, as card {} and card {} of the table",
+                first + 1,
+                second + 1
+            ),
+            TableError::Missing { id, next } => {
+                write!(
+                    f,
+                    "card {id} is missing, card {} of the table comes after it",
+                    next + 1
+                )
+            }
+        }
     }
 }
 
+impl Error for TableError {}
+
+/// All cards of a game, ordered by id. The ids are unique and have no gaps, in
+/// whatever order the cards were given.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CardTable {
+    cards: Vec<Card>,
+}
+
+impl CardTable {
+    pub fn new(cards: Vec<Card>) -> Result<Self, TableError> {
+        let mut order = (0..cards.len()).collect::<Vec<_>>();
+        order.sort_by_key(|position| (cards[*position].id, *position));
+
+        for pair in order.windows(2) {
+            let (previous, id) = (cards[pair[0]].id, cards[pair[1]].id);
+            if id == previous {
+                return Err(TableError::Duplicate {
+                    id,
+                    first: pair[0],
+                    second: pair[1],
+                });
+            }
+            if id > previous + 1 {
+                return Err(TableError::Missing {
+                    id: previous + 1,
+                    next: pair[1],
+                });
+            }
+        }
+
+        let mut cards = cards;
+        cards.sort_by_key(|card| card.id);
+        Ok(CardTable { cards })
+    }
+
+    /// The cards, ordered by id.
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
+    pub fn get(&self, id: u32) -> Option<&Card> {
+        self.index_of(id).map(|index| &self.cards[index])
+    }
+
+    fn index_of(&self, id: u32) -> Option<usize> {
+        let index = id.checked_sub(self.cards.first()?.id)? as usize;
+        (index < self.cards.len()).then_some(index)
+    }
+}
+
+/// Points a table error at the id of the offending line of `inp`.
+fn table_error(inp: &str, err: TableError) -> ParseError {
+    let (position, expected) = match err {
+        TableError::Duplicate { id, first, second } => (
+            second,
+            format!(
+                "a card id other than {id}, which line {} already uses",
+                first + 1
+            ),
+        ),
+        TableError::Missing { id, next } => (next, format!("card {id} before this card")),
+    };
+
+    let line = inp.lines().nth(position).unwrap_or_default();
+    let id = line
+        .split_once(':')
+        .and_then(|(header, _)| header.strip_prefix("Card "))
+        .map(str::trim)
+        .unwrap_or(line);
+
+    ParseError::at(line, id, expected)
+        .on_line(position + 1, line)
+        .in_day(4)
+}
+
 #[aoc_generator(day04)]
-fn generator_day0(inp: &str) -> Vec<Card> {
-    inp.lines()
-        .map(|line| line.parse::<Card>().expect("invalid input"))
-        .collect()
+fn generator_day0(inp: &str) -> Result<CardTable, ParseError> {
+    let cards = parse_lines(4, inp, str::parse)?;
+    CardTable::new(cards).map_err(|err| table_error(inp, err))
+}
+
+/// How many points a card with a given number of matches is worth.
+pub trait ScoringRule {
+    /// The points for `matches` matches, `None` if they don't fit into 64 bits.
+    fn score(&self, matches: u32) -> Option<u64>;
+}
+
+/// The puzzle's rule: one point for the first match, doubled for every further one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Doubling;
+
+impl ScoringRule for Doubling {
+    fn score(&self, matches: u32) -> Option<u64> {
+        match matches {
+            0 => Some(0),
+            matches => 1u64.checked_shl(matches - 1),
+        }
+    }
+}
+
+/// A fixed number of points per match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Linear {
+    pub per_match: u64,
+}
+
+impl ScoringRule for Linear {
+    fn score(&self, matches: u32) -> Option<u64> {
+        self.per_match.checked_mul(matches as u64)
+    }
+}
+
+/// The `matches`-th Fibonacci number: 0, 1, 1, 2, 3, 5, ...
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Fibonacci;
+
+impl ScoringRule for Fibonacci {
+    fn score(&self, matches: u32) -> Option<u64> {
+        if matches == 0 {
+            return Some(0);
+        }
+        let (mut previous, mut current) = (0u64, 1u64);
+        for _ in 1..matches {
+            (previous, current) = (current, previous.checked_add(current)?);
+        }
+        Some(current)
+    }
+}
+
+/// Another rule, but never more than `cap` points. Scores too large for 64 bits are capped as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capped<R> {
+    pub rule: R,
+    pub cap: u64,
+}
+
+impl<R: ScoringRule> ScoringRule for Capped<R> {
+    fn score(&self, matches: u32) -> Option<u64> {
+        Some(
+            self.rule
+                .score(matches)
+                .map_or(self.cap, |score| score.min(self.cap)),
+        )
+    }
+}
+
+/// A rule given as a closure from the number of matches to the points.
+#[derive(Debug, Clone, Copy)]
+pub struct ScoringFn<F>(pub F);
+
+impl<F: Fn(u32) -> Option<u64>> ScoringRule for ScoringFn<F> {
+    fn score(&self, matches: u32) -> Option<u64> {
+        (self.0)(matches)
+    }
+}
+
+/// The score of card `card` or the sum up to it does not fit into 64 bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScoreError {
+    pub card: u32,
+}
+
+impl Display for ScoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the score overflows at card {}", self.card)
+    }
+}
+
+impl Error for ScoreError {}
+
+/// Sums the points of all cards under `rule`.
+pub fn total_score(table: &CardTable, rule: &impl ScoringRule) -> Result<u64, ScoreError> {
+    table.cards().iter().try_fold(0u64, |total, card| {
+        rule.score(card.matches())
+            .and_then(|score| total.checked_add(score))
+            .ok_or(ScoreError { card: card.id })
+    })
 }
 
 #[aoc(day04, part1)]
-fn day04_part_1(cards: &[Card]) -> u32 {
-    cards
-        .iter()
-        .filter_map(|card| {
-            let amount = card
-                .own
-                .iter()
-                .filter(|val| card.wins.contains(val))
-                .count();
+fn day04_part_1(table: &CardTable) -> Result<u64, ScoreError> {
+    total_score(table, &Doubling)
+}
+
+/// What to do with a card that wins copies of cards past the end of the table.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub enum CopyMode {
+    /// Only copy the cards that exist, as the puzzle promises never to go past the end.
+    #[default]
+    Clamp,
+    /// Reject the input.
+    Strict,
+}
 
-            if amount > 0 {
-                Some(2u32.pow(amount as u32 - 1))
-            } else {
-                None
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CopyError {
+    /// The card `card` has `matches` matches, but only `remaining` cards follow it.
+    PastEnd {
+        card: u32,
+        matches: u32,
+        remaining: usize,
+    },
+    /// The number of copies of `card` does not fit into 64 bits.
+    Overflow { card: u32 },
+    /// Every card fits into 64 bits on its own, but the total number of cards does not.
+    TotalOverflow,
+}
+
+impl Display for CopyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CopyError::PastEnd {
+                card,
+                matches,
+                remaining,
+            } => write!(
+                f,
+                "card {card} wins copies of the next {matches} cards, but only {remaining} follow it"
+            ),
+            CopyError::Overflow { card } => {
+                write!(f, "the number of copies of card {card} overflows")
             }
-        })
-        .sum()
+            CopyError::TotalOverflow => write!(f, "the total number of cards overflows"),
+        }
+    }
 }
 
-#[aoc(day04, part2)]
-fn day04_part_2(cards: &[Card]) -> u32 {
-    let mut map = HashMap::new();
+impl Error for CopyError {}
 
-    cards.iter().enumerate().for_each(|(index, card)| {
-        let amount = card
-            .own
-            .iter()
-            .filter(|val| card.wins.contains(val))
-            .count();
+/// Hands out the copies every card wins, calling `on_copy(from, to, copies)` with the
+/// table indices of the winning and the copied card. Returns the copies of every card.
+fn propagate(
+    table: &CardTable,
+    mode: CopyMode,
+    mut on_copy: impl FnMut(usize, usize, u64),
+) -> Result<Vec<u64>, CopyError> {
+    let mut copies = vec![1u64; table.len()];
 
-        let current_val = map.get(&index).cloned().unwrap_or(1);
-        map.insert(index, current_val);
+    for (index, card) in table.cards().iter().enumerate() {
+        let current = copies[index];
 
-        for i in 0..amount {
-            let key = index + i + 1;
-            let val = map.get(&key).cloned().unwrap_or(1);
-            map.insert(key, val + current_val);
+        for offset in 1..=card.matches() {
+            let won = card
+                .id
+                .checked_add(offset)
+                .and_then(|id| table.index_of(id));
+            let Some(won) = won else {
+                if mode == CopyMode::Strict {
+                    return Err(CopyError::PastEnd {
+                        card: card.id,
+                        matches: card.matches(),
+                        remaining: (offset - 1) as usize,
+                    });
+                }
+                break;
+            };
+
+            copies[won] = copies[won]
+                .checked_add(current)
+                .ok_or(CopyError::Overflow {
+                    card: table.cards[won].id,
+                })?;
+            on_copy(index, won, current);
         }
-    });
-    map.values().sum()
+    }
+
+    Ok(copies)
+}
+
+/// How many copies of every card there are once all wins are handed out, ordered by id.
+/// A card with id `id` and `n` matches wins copies of the cards `id + 1` to `id + n`.
+pub fn count_copies(table: &CardTable, mode: CopyMode) -> Result<Vec<u64>, CopyError> {
+    propagate(table, mode, |_, _, _| {})
+}
+
+/// How a single card fared in the copy cascade.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CardReport {
+    pub id: u32,
+    pub matches: u32,
+    /// The final number of this card, the original included.
+    pub copies: u64,
+    /// The earlier cards that won copies of this one, as `(id, copies)`.
+    pub contributors: Vec<(u32, u64)>,
+}
+
+impl CardReport {
+    pub fn to_json(&self) -> String {
+        let contributors = self.contributors.iter().map(|(id, copies)| {
+            json::object([("card", id.to_string()), ("copies", copies.to_string())])
+        });
+
+        json::object([
+            ("id", self.id.to_string()),
+            ("matches", self.matches.to_string()),
+            ("copies", self.copies.to_string()),
+            ("contributors", json::array(contributors)),
+        ])
+    }
+}
+
+/// The whole copy cascade, one report per card ordered by id.
+pub fn copy_report(table: &CardTable, mode: CopyMode) -> Result<Vec<CardReport>, CopyError> {
+    let mut contributors = vec![vec![]; table.len()];
+    let copies = propagate(table, mode, |from, to, copies| {
+        contributors[to].push((table.cards[from].id, copies));
+    })?;
+
+    Ok(table
+        .cards()
+        .iter()
+        .zip(copies)
+        .zip(contributors)
+        .map(|((card, copies), contributors)| CardReport {
+            id: card.id,
+            matches: card.matches(),
+            copies,
+            contributors,
+        })
+        .collect())
+}
+
+/// The reports as CSV with a header row. Contributors are listed as `id:copies`,
+/// separated by `;`.
+pub fn copy_report_csv(reports: &[CardReport]) -> String {
+    let mut csv = String::from("id,matches,copies,contributors\n");
+    for report in reports {
+        let contributors = report
+            .contributors
+            .iter()
+            .map(|(id, copies)| format!("{id}:{copies}"))
+            .collect::<Vec<_>>();
+        csv.push_str(&format!(
+            "{},{},{},{}\n",
+            report.id,
+            report.matches,
+            report.copies,
+            contributors.join(";")
+        ));
+    }
+    csv
+}
+
+/// The reports as a JSON array.
+pub fn copy_report_json(reports: &[CardReport]) -> String {
+    json::array(reports.iter().map(CardReport::to_json))
+}
+
+/// The total number of cards, originals and copies.
+pub fn total_cards(table: &CardTable, mode: CopyMode) -> Result<u64, CopyError> {
+    let copies = count_copies(table, mode)?;
+
+    copies
+        .iter()
+        .try_fold(0u64, |total, count| total.checked_add(*count))
+        .ok_or(CopyError::TotalOverflow)
+}
+
+#[aoc(day04, part2)]
+fn day04_part_2(table: &CardTable) -> Result<u64, CopyError> {
+    total_cards(table, CopyMode::Clamp)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_rng::Rng;
 
     #[test]
     fn test_parse_card() {
@@ -108,14 +549,30 @@ mod tests {
 
         assert_eq!(
             card,
-            Ok(Card {
-                id: 42,
-                wins: vec![1337, 89, 123],
-                own: vec![42, 17, 11]
-            })
+            Ok(Card::new(42, vec![1337, 89, 123], vec![42, 17, 11]))
         )
     }
 
+    #[test]
+    fn test_parse_card_errors() {
+        let err = Card::from_str("Card 42: 1337 8x9 | 42").unwrap_err();
+        assert_eq!(err.span(), 14..17);
+        assert_eq!(err.expected(), "a number");
+
+        let err = Card::from_str("Card x: 1 | 2").unwrap_err();
+        assert_eq!(err.span(), 5..6);
+
+        let err = Card::from_str("Cart 1: 1 | 2").unwrap_err();
+        assert_eq!(err.span(), 0..6);
+
+        let err = Card::from_str("Card 1: 1 2 3").unwrap_err();
+        assert_eq!(err.span(), 7..13);
+
+        let err = generator_day0("Card 1: 1 | 2\nCard 2 1 | 2").unwrap_err();
+        assert_eq!(err.line(), Some(2));
+        assert_eq!(err.span(), 12..12);
+    }
+
     const INPUT: &str = "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
 Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
 Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
@@ -125,13 +582,270 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
 
     #[test]
     fn test_day04_part_1() {
-        let gen = generator_day0(INPUT);
-        assert_eq!(day04_part_1(&gen), 13);
+        let gen = generator_day0(INPUT).unwrap();
+        assert_eq!(day04_part_1(&gen), Ok(13));
     }
 
     #[test]
     fn test_day04_part_2() {
-        let gen = generator_day0(INPUT);
-        assert_eq!(day04_part_2(&gen), 30);
+        let gen = generator_day0(INPUT).unwrap();
+        assert_eq!(day04_part_2(&gen), Ok(30));
+    }
+
+    #[test]
+    fn test_copies_past_end() {
+        let gen = generator_day0(
+            "Card 1: 1 2 | 1
+Card 2: 1 2 3 | 1 2 3",
+        )
+        .unwrap();
+
+        // card 2 would copy three cards that do not exist
+        assert_eq!(count_copies(&gen, CopyMode::Clamp), Ok(vec![1, 2]));
+        assert_eq!(total_cards(&gen, CopyMode::Clamp), Ok(3));
+
+        let err = total_cards(&gen, CopyMode::Strict).unwrap_err();
+        assert_eq!(
+            err,
+            CopyError::PastEnd {
+                card: 2,
+                matches: 3,
+                remaining: 0
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "card 2 wins copies of the next 3 cards, but only 0 follow it"
+        );
+
+        let gen = generator_day0(INPUT).unwrap();
+        assert_eq!(total_cards(&gen, CopyMode::Strict), Ok(30));
+    }
+
+    #[test]
+    fn test_copies_overflow() {
+        // every card doubles the count of all following ones
+        let table = |len: u32| {
+            let cards = (1..=len)
+                .map(|id| Card::new(id, vec![1], vec![1; (len - id) as usize]))
+                .collect();
+            CardTable::new(cards).unwrap()
+        };
+
+        let copies = count_copies(&table(64), CopyMode::Strict).unwrap();
+        assert_eq!(copies.last(), Some(&(1 << 63)));
+        assert_eq!(total_cards(&table(64), CopyMode::Strict), Ok(u64::MAX));
+        assert_eq!(
+            count_copies(&table(70), CopyMode::Strict),
+            Err(CopyError::Overflow { card: 65 })
+        );
+
+        // one more card in front that wins nothing tips the total over
+        let mut cards = vec![Card::new(1, vec![1], vec![])];
+        cards.extend((2..=65).map(|id| Card::new(id, vec![1], vec![1; (65 - id) as usize])));
+        let table = CardTable::new(cards).unwrap();
+        assert!(count_copies(&table, CopyMode::Strict).is_ok());
+        let err = total_cards(&table, CopyMode::Strict).unwrap_err();
+        assert_eq!(err, CopyError::TotalOverflow);
+        assert_eq!(err.to_string(), "the total number of cards overflows");
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let mut rng = Rng::new(4);
+
+        for _ in 0..200 {
+            let card = Card::new(
+                rng.below(300) as u32,
+                rng.vec(0, 10, |rng| rng.below(200) as u32),
+                rng.vec(0, 25, |rng| rng.below(200) as u32),
+            );
+
+            assert_eq!(Card::from_str(&card.to_string()), Ok(card));
+        }
+
+        let input = "Card 42: 1337 89 123 | 42 17 11";
+        assert_eq!(Card::from_str(input).unwrap().to_string(), input);
+    }
+
+    #[test]
+    fn test_matches() {
+        let mut rng = Rng::new(20);
+
+        for bound in [100, 128, 129, 1 << 20] {
+            for _ in 0..100 {
+                let wins = rng.vec(0, 10, |rng| rng.below(bound) as u32);
+                let own = rng.vec(0, 25, |rng| rng.below(bound) as u32);
+                let expected = own.iter().filter(|n| wins.contains(n)).count() as u32;
+
+                assert_eq!(Card::new(1, wins, own).matches(), expected);
+            }
+        }
+
+        let card = Card::new(1, vec![3, 200], vec![3, 3, 200, 7]);
+        assert!(matches!(card.winning, WinningSet::Large(_)));
+        assert_eq!(card.matches(), 3);
+
+        let card = Card::new(1, vec![0, 127], vec![127, 128, 0]);
+        assert!(matches!(card.winning, WinningSet::Small(_)));
+        assert_eq!(card.matches(), 2);
+    }
+
+    #[test]
+    fn test_card_table() {
+        // shuffled cards are put in order and copy by id
+        let shuffled = INPUT.lines().rev().collect::<Vec<_>>().join("\n");
+        let table = generator_day0(&shuffled).unwrap();
+        assert_eq!(table, generator_day0(INPUT).unwrap());
+        assert_eq!(table.get(3).map(Card::matches), Some(2));
+        assert_eq!(table.get(7), None);
+        assert_eq!(table.get(0), None);
+        assert_eq!(
+            count_copies(&table, CopyMode::Strict),
+            Ok(vec![1, 2, 4, 8, 14, 1])
+        );
+
+        let card = |id| Card::new(id, vec![], vec![]);
+        assert_eq!(
+            CardTable::new(vec![card(2), card(1), card(2)]),
+            Err(TableError::Duplicate {
+                id: 2,
+                first: 0,
+                second: 2
+            })
+        );
+        assert_eq!(
+            CardTable::new(vec![card(1), card(4), card(2)]),
+            Err(TableError::Missing { id: 3, next: 1 })
+        );
+        assert_eq!(
+            CardTable::new(vec![card(3), card(5)])
+                .unwrap_err()
+                .to_string(),
+            "card 4 is missing, card 2 of the table comes after it"
+        );
+
+        // the table does not have to start at card 1
+        let table = CardTable::new(vec![card(1), card(0)]).unwrap();
+        assert_eq!(table.get(0), Some(&card(0)));
+        assert_eq!(table.get(2), None);
+        assert_eq!(CardTable::new(vec![]).map(|table| table.len()), Ok(0));
+    }
+
+    #[test]
+    fn test_card_table_errors() {
+        let err = generator_day0("Card 1: 1 | 1\nCard 2: 1 | 1\nCard  1: 2 | 3").unwrap_err();
+        assert_eq!(err.line(), Some(3));
+        assert_eq!(err.span(), 6..7);
+        assert_eq!(
+            err.expected(),
+            "a card id other than 1, which line 1 already uses"
+        );
+
+        let err = generator_day0("Card 1: 1 | 1\nCard 3: 1 | 1").unwrap_err();
+        assert_eq!(err.line(), Some(2));
+        assert_eq!(err.span(), 5..6);
+        assert_eq!(err.expected(), "card 2 before this card");
+    }
+
+    #[test]
+    fn test_scoring_rules() {
+        let scores = |rule: &dyn Fn(u32) -> Option<u64>| (0..7).map(rule).collect::<Vec<_>>();
+        let all = |scores: &[u64]| scores.iter().copied().map(Some).collect::<Vec<_>>();
+
+        assert_eq!(
+            scores(&|m| Doubling.score(m)),
+            all(&[0, 1, 2, 4, 8, 16, 32])
+        );
+        let linear = Linear { per_match: 3 };
+        assert_eq!(scores(&|m| linear.score(m)), all(&[0, 3, 6, 9, 12, 15, 18]));
+        assert_eq!(scores(&|m| Fibonacci.score(m)), all(&[0, 1, 1, 2, 3, 5, 8]));
+        let capped = Capped {
+            rule: Doubling,
+            cap: 10,
+        };
+        assert_eq!(scores(&|m| capped.score(m)), all(&[0, 1, 2, 4, 8, 10, 10]));
+        let squares = ScoringFn(|m: u32| Some(m as u64 * m as u64));
+        assert_eq!(
+            scores(&|m| squares.score(m)),
+            all(&[0, 1, 4, 9, 16, 25, 36])
+        );
+
+        assert_eq!(Doubling.score(64), Some(1 << 63));
+        assert_eq!(Doubling.score(65), None);
+        assert_eq!(Fibonacci.score(93), Some(12200160415121876738));
+        assert_eq!(Fibonacci.score(94), None);
+        assert_eq!(
+            Linear {
+                per_match: u64::MAX
+            }
+            .score(2),
+            None
+        );
+        assert_eq!(
+            Capped {
+                rule: Fibonacci,
+                cap: 7
+            }
+            .score(500),
+            Some(7)
+        );
+    }
+
+    #[test]
+    fn test_total_score() {
+        let gen = generator_day0(INPUT).unwrap();
+
+        assert_eq!(
+            total_score(&gen, &Linear { per_match: 1 }),
+            Ok(4 + 2 + 2 + 1)
+        );
+        assert_eq!(total_score(&gen, &Fibonacci), Ok(3 + 1 + 1 + 1));
+
+        let table = CardTable::new(vec![
+            Card::new(1, vec![1], vec![1; 64]),
+            Card::new(2, vec![1], vec![1; 64]),
+        ])
+        .unwrap();
+        assert_eq!(total_score(&table, &Doubling), Err(ScoreError { card: 2 }));
+        assert_eq!(
+            total_score(&table, &ScoringFn(|_| None)),
+            Err(ScoreError { card: 1 })
+        );
+    }
+
+    #[test]
+    fn test_copy_report() {
+        let gen = generator_day0(INPUT).unwrap();
+        let report = copy_report(&gen, CopyMode::Strict).unwrap();
+
+        assert_eq!(
+            report.iter().map(|card| card.copies).collect::<Vec<_>>(),
+            count_copies(&gen, CopyMode::Strict).unwrap()
+        );
+        assert_eq!(
+            report[4],
+            CardReport {
+                id: 5,
+                matches: 0,
+                copies: 14,
+                contributors: vec![(1, 1), (3, 4), (4, 8)],
+            }
+        );
+        assert_eq!(report[0].contributors, vec![]);
+
+        assert_eq!(
+            copy_report_csv(&report[..3]),
+            "id,matches,copies,contributors
+1,4,1,
+2,2,2,1:1
+3,2,4,1:1;2:2
+"
+        );
+        assert_eq!(
+            copy_report_json(&report[1..3]),
+            r#"[{"id":2,"matches":2,"copies":2,"contributors":[{"card":1,"copies":1}]},{"id":3,"matches":2,"copies":4,"contributors":[{"card":1,"copies":1},{"card":2,"copies":2}]}]"#
+        );
+        assert_eq!(copy_report_json(&[]), "[]");
     }
 }
@@ -1,8 +1,10 @@
-use std::{num::ParseIntError, str::FromStr, thread};
+use std::{fmt::Display, ops::Range, str::FromStr};
 
 use aoc_runner_derive::{aoc, aoc_generator};
 
-#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+use crate::parse_error::ParseError;
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub enum Ingredients {
     Seed,
     Soil,
@@ -15,7 +17,7 @@ pub enum Ingredients {
 }
 
 impl FromStr for Ingredients {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         use Ingredients::*;
@@ -28,11 +30,32 @@ impl FromStr for Ingredients {
             "temperature" => Ok(Temperature),
             "humidity" => Ok(Humidity),
             "location" => Ok(Location),
-            _ => Err(()),
+            _ => Err(ParseError::at(
+                s,
+                s,
+                "a category (seed, soil, fertilizer, water, light, temperature, humidity or location)",
+            )),
         }
     }
 }
 
+impl Display for Ingredients {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Ingredients::*;
+        let name = match self {
+            Seed => "seed",
+            Soil => "soil",
+            Fertilizer => "fertilizer",
+            Water => "water",
+            Light => "light",
+            Temperature => "temperature",
+            Humidity => "humidity",
+            Location => "location",
+        };
+        f.write_str(name)
+    }
+}
+
 #[derive(Debug, Default, Clone, Hash, PartialEq, Eq)]
 pub struct ConversionRange {
     src_start: usize,
@@ -47,17 +70,71 @@ impl ConversionRange {
         }
         None
     }
+
+    pub fn invert(&self, n: usize) -> Option<usize> {
+        if n >= self.dest_start && n < self.dest_start + self.length {
+            return Some(self.src_start + (n - self.dest_start));
+        }
+        None
+    }
+
+    /// Splits `interval` into the part covered by this range, already shifted into the
+    /// destination, and the uncovered leftovers on either side.
+    pub fn convert_interval(
+        &self,
+        interval: Range<usize>,
+    ) -> (Option<Range<usize>>, Vec<Range<usize>>) {
+        let src_end = self.src_start + self.length;
+        let start = interval.start.max(self.src_start);
+        let end = interval.end.min(src_end);
+
+        if start >= end {
+            return (None, vec![interval]);
+        }
+
+        let mut leftovers = vec![];
+        if interval.start < start {
+            leftovers.push(interval.start..start);
+        }
+        if end < interval.end {
+            leftovers.push(end..interval.end);
+        }
+
+        let mapped =
+            self.dest_start + (start - self.src_start)..self.dest_start + (end - self.src_start);
+        (Some(mapped), leftovers)
+    }
 }
 
 impl FromStr for ConversionRange {
-    type Err = ParseIntError;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut numbers = s.split(' ');
 
-        let dest_start = numbers.next().expect("invalid input").parse::<usize>()?;
-        let src_start = numbers.next().expect("invalid input").parse::<usize>()?;
-        let length = numbers.next().expect("invalid input").parse::<usize>()?;
+        let mut next_number = |expected: &str| match numbers.next() {
+            Some(num) => num
+                .parse::<usize>()
+                .map_err(|_| ParseError::at(s, num, expected)),
+            None => Err(ParseError::at_end(s, expected)),
+        };
+
+        let dest_start = next_number("a destination range start")?;
+        let src_start = next_number("a source range start")?;
+        let length = next_number("a range length")?;
+
+        if src_start.max(dest_start).checked_add(length).is_none() {
+            let length = s.split(' ').nth(2).unwrap_or(s);
+            return Err(ParseError::at(
+                s,
+                length,
+                "a range length that does not overflow",
+            ));
+        }
+
+        if let Some(rest) = numbers.next() {
+            return Err(ParseError::at(s, rest, "the end of the line"));
+        }
 
         Ok(ConversionRange {
             src_start,
@@ -67,98 +144,754 @@ impl FromStr for ConversionRange {
     }
 }
 
+impl Display for ConversionRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.dest_start, self.src_start, self.length)
+    }
+}
+
+/// Two ranges of the `from`-to-`to` map, at the (0-based) positions `first` and `second`,
+/// convert some of the same numbers.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct OverlapError {
+    pub from: Ingredients,
+    pub to: Ingredients,
+    pub first: usize,
+    pub second: usize,
+}
+
+impl Display for OverlapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ranges {} and {} of the {}-to-{} map overlap",
+            self.first + 1,
+            self.second + 1,
+            self.from,
+            self.to
+        )
+    }
+}
+
+impl std::error::Error for OverlapError {}
+
+/// A map from one category to the next. No two of its non-empty ranges overlap, so
+/// every number is converted by at most one of them.
+///
+/// The ranges keep the order they were read in, so that the map prints back to the same
+/// text; lookups go through `index`, which holds the positions of the non-empty ranges
+/// sorted by their source start.
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct ConversionMap {
     from: Ingredients,
     to: Ingredients,
     ranges: Vec<ConversionRange>,
+    index: Vec<usize>,
 }
 
 impl ConversionMap {
-    fn append(&mut self, range: ConversionRange) {
-        self.ranges.push(range);
+    pub fn new(
+        from: Ingredients,
+        to: Ingredients,
+        ranges: Vec<ConversionRange>,
+    ) -> Result<Self, OverlapError> {
+        // empty ranges convert nothing, so they are left out of the lookups altogether
+        let mut index = (0..ranges.len())
+            .filter(|i| ranges[*i].length > 0)
+            .collect::<Vec<_>>();
+        index.sort_by_key(|i| ranges[*i].src_start);
+
+        // once sorted, any overlap shows up between neighbours
+        for pair in index.windows(2) {
+            let (a, b) = (&ranges[pair[0]], &ranges[pair[1]]);
+            if b.src_start < a.src_start + a.length {
+                return Err(OverlapError {
+                    from,
+                    to,
+                    first: pair[0].min(pair[1]),
+                    second: pair[0].max(pair[1]),
+                });
+            }
+        }
+
+        Ok(ConversionMap {
+            from,
+            to,
+            ranges,
+            index,
+        })
+    }
+
+    /// The ranges in the order they were given.
+    pub fn ranges(&self) -> &[ConversionRange] {
+        &self.ranges
     }
 
     pub fn convert(&self, num: usize) -> usize {
+        let candidate = self
+            .index
+            .partition_point(|i| self.ranges[*i].src_start <= num);
+        candidate
+            .checked_sub(1)
+            .and_then(|position| self.ranges[self.index[position]].convert(num))
+            .unwrap_or(num)
+    }
+
+    pub fn convert_intervals(&self, intervals: &[Range<usize>]) -> Vec<Range<usize>> {
+        let mut pending = intervals
+            .iter()
+            .filter(|interval| !interval.is_empty())
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut converted = vec![];
+
+        // ranges don't overlap, so only the leftovers of one range are offered to the next
         for range in &self.ranges {
-            if let Some(res) = range.convert(num) {
-                return res;
+            let mut leftovers = vec![];
+            for interval in pending {
+                let (mapped, mut rest) = range.convert_interval(interval);
+                converted.extend(mapped);
+                leftovers.append(&mut rest);
+            }
+            pending = leftovers;
+        }
+
+        converted.append(&mut pending);
+        converted
+    }
+
+    /// Every source number that ends up at `num`.
+    pub fn invert(&self, num: usize) -> Vec<usize> {
+        let mut sources = self
+            .ranges
+            .iter()
+            .filter_map(|range| range.invert(num))
+            .filter(|src| self.convert(*src) == num)
+            .collect::<Vec<_>>();
+
+        if self.ranges.iter().all(|range| range.convert(num).is_none()) {
+            sources.push(num);
+        }
+
+        sources.sort_unstable();
+        sources.dedup();
+        sources
+    }
+
+    pub fn invert_intervals(&self, targets: &[Range<usize>]) -> Vec<Range<usize>> {
+        PiecewiseMap::from(self).invert_intervals(targets)
+    }
+
+    pub fn breakpoints(&self) -> Vec<usize> {
+        let mut points = self
+            .ranges
+            .iter()
+            .flat_map(|range| [range.src_start, range.src_start + range.length])
+            .collect::<Vec<_>>();
+        points.sort_unstable();
+        points.dedup();
+        points
+    }
+
+    pub fn compose(&self, next: &ConversionMap) -> PiecewiseMap {
+        PiecewiseMap::from(self).compose(&PiecewiseMap::from(next))
+    }
+}
+
+fn intersect(a: &Range<usize>, b: &Range<usize>) -> Option<Range<usize>> {
+    let start = a.start.max(b.start);
+    let end = a.end.min(b.end);
+    (start < end).then_some(start..end)
+}
+
+/// Splits `[0, usize::MAX)` at the given ascending breakpoints.
+fn pieces(breakpoints: &[usize]) -> Vec<Range<usize>> {
+    let mut pieces = vec![];
+    let mut start = 0;
+
+    for &point in breakpoints {
+        if point > start {
+            pieces.push(start..point);
+            start = point;
+        }
+    }
+    pieces.push(start..usize::MAX);
+
+    pieces
+}
+
+/// A conversion with sorted, disjoint segments. Everything outside the segments maps to itself.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct PiecewiseMap {
+    from: Ingredients,
+    to: Ingredients,
+    segments: Vec<ConversionRange>,
+}
+
+impl PiecewiseMap {
+    fn push(&mut self, segment: ConversionRange) {
+        if segment.length == 0 || segment.src_start == segment.dest_start {
+            return;
+        }
+
+        if let Some(last) = self.segments.last_mut() {
+            if last.src_start + last.length == segment.src_start
+                && last.dest_start + last.length == segment.dest_start
+            {
+                last.length += segment.length;
+                return;
+            }
+        }
+
+        self.segments.push(segment);
+    }
+
+    pub fn segments(&self) -> &[ConversionRange] {
+        &self.segments
+    }
+
+    pub fn convert(&self, num: usize) -> usize {
+        let index = self
+            .segments
+            .partition_point(|segment| segment.src_start + segment.length <= num);
+
+        self.segments
+            .get(index)
+            .and_then(|segment| segment.convert(num))
+            .unwrap_or(num)
+    }
+
+    pub fn breakpoints(&self) -> Vec<usize> {
+        let mut points = self
+            .segments
+            .iter()
+            .flat_map(|segment| [segment.src_start, segment.src_start + segment.length])
+            .collect::<Vec<_>>();
+        points.dedup();
+        points
+    }
+
+    /// Returns every source interval that lands inside one of `targets`, sorted by start.
+    /// Each returned interval maps linearly into the destination.
+    pub fn invert_intervals(&self, targets: &[Range<usize>]) -> Vec<Range<usize>> {
+        let mut sources = vec![];
+
+        for target in targets.iter().filter(|target| !target.is_empty()) {
+            for segment in &self.segments {
+                let dest = segment.dest_start..segment.dest_start + segment.length;
+                if let Some(hit) = intersect(target, &dest) {
+                    sources.push(
+                        segment.src_start + (hit.start - segment.dest_start)
+                            ..segment.src_start + (hit.end - segment.dest_start),
+                    );
+                }
+            }
+
+            // numbers outside of every segment map onto themselves
+            let mut start = target.start;
+            for segment in &self.segments {
+                let src_end = segment.src_start + segment.length;
+                if src_end <= start {
+                    continue;
+                }
+                if segment.src_start >= target.end {
+                    break;
+                }
+                if segment.src_start > start {
+                    sources.push(start..segment.src_start);
+                }
+                start = src_end;
+            }
+            if start < target.end {
+                sources.push(start..target.end);
+            }
+        }
+
+        sources.sort_by_key(|source| source.start);
+        sources
+    }
+
+    /// Returns the map that applies `self` first and `next` afterwards.
+    pub fn compose(&self, next: &PiecewiseMap) -> PiecewiseMap {
+        let mut composed = PiecewiseMap {
+            from: self.from,
+            to: next.to,
+            segments: vec![],
+        };
+
+        let cuts = next.breakpoints();
+
+        for piece in pieces(&self.breakpoints()) {
+            let image_start = self.convert(piece.start);
+            let image_end = image_start + piece.len();
+
+            let first_cut = cuts.partition_point(|&cut| cut <= image_start);
+            let inner_cuts = cuts[first_cut..]
+                .iter()
+                .cloned()
+                .take_while(|&cut| cut < image_end);
+
+            let mut start = image_start;
+            for end in inner_cuts.chain([image_end]) {
+                composed.push(ConversionRange {
+                    src_start: piece.start + (start - image_start),
+                    dest_start: next.convert(start),
+                    length: end - start,
+                });
+                start = end;
+            }
+        }
+
+        composed
+    }
+}
+
+impl From<&ConversionMap> for PiecewiseMap {
+    fn from(map: &ConversionMap) -> Self {
+        let mut piecewise = PiecewiseMap {
+            from: map.from,
+            to: map.to,
+            segments: vec![],
+        };
+
+        for piece in pieces(&map.breakpoints()) {
+            piecewise.push(ConversionRange {
+                src_start: piece.start,
+                dest_start: map.convert(piece.start),
+                length: piece.len(),
+            });
+        }
+
+        piecewise
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteError {
+    Broken {
+        from: Ingredients,
+        to: Ingredients,
+        reachable: Vec<Ingredients>,
+    },
+    Ambiguous {
+        from: Ingredients,
+        to: Ingredients,
+        routes: Vec<Vec<Ingredients>>,
+    },
+}
+
+impl Display for RouteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RouteError::Broken {
+                from,
+                to,
+                reachable,
+            } => {
+                write!(f, "no chain of maps converts {from} to {to}")?;
+                if reachable.is_empty() {
+                    write!(f, " (no map starts at {from})")
+                } else {
+                    let reachable = reachable
+                        .iter()
+                        .map(|ingredient| ingredient.to_string())
+                        .collect::<Vec<_>>();
+                    write!(f, " (only reaches {})", reachable.join(", "))
+                }
+            }
+            RouteError::Ambiguous { from, to, routes } => {
+                writeln!(f, "more than one chain of maps converts {from} to {to}:")?;
+                for route in routes {
+                    let route = route
+                        .iter()
+                        .map(|ingredient| ingredient.to_string())
+                        .collect::<Vec<_>>();
+                    writeln!(f, "  {}", route.join(" -> "))?;
+                }
+                Ok(())
             }
         }
-        num
+    }
+}
+
+impl std::error::Error for RouteError {}
+
+/// The seeds can't be read as (start, length) pairs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SeedError {
+    /// The last seed, `start`, has no length to go with it.
+    MissingLength { start: usize },
+    /// The range of `length` seeds from `start` runs past `usize::MAX`.
+    Overflow { start: usize, length: usize },
+}
+
+impl Display for SeedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SeedError::MissingLength { start } => {
+                write!(f, "the seed range starting at {start} has no length")
+            }
+            SeedError::Overflow { start, length } => write!(
+                f,
+                "the seed range of length {length} starting at {start} runs past {}",
+                usize::MAX
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SeedError {}
+
+/// A resolved chain of maps between two categories, found once by [`Input::route`] and
+/// reused for every conversion along it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route<'a> {
+    from: Ingredients,
+    maps: Vec<&'a ConversionMap>,
+}
+
+impl Route<'_> {
+    pub fn maps(&self) -> &[&ConversionMap] {
+        &self.maps
+    }
+
+    pub fn convert(&self, num: usize) -> usize {
+        self.maps.iter().fold(num, |num, map| map.convert(num))
+    }
+
+    pub fn convert_intervals(&self, intervals: &[Range<usize>]) -> Vec<Range<usize>> {
+        self.maps.iter().fold(intervals.to_vec(), |intervals, map| {
+            map.convert_intervals(&intervals)
+        })
+    }
+
+    /// Composes the whole chain into a single map.
+    pub fn compose(&self) -> PiecewiseMap {
+        let identity = PiecewiseMap {
+            from: self.from,
+            to: self.from,
+            segments: vec![],
+        };
+
+        self.maps.iter().fold(identity, |composed, next| {
+            composed.compose(&PiecewiseMap::from(*next))
+        })
+    }
+
+    /// Every number that ends up at `num` at the end of the chain.
+    pub fn invert(&self, num: usize) -> Vec<usize> {
+        let mut sources = self.maps.iter().rev().fold(vec![num], |nums, map| {
+            nums.iter().flat_map(|num| map.invert(*num)).collect()
+        });
+        sources.sort_unstable();
+        sources
+    }
+
+    /// Every interval that ends up inside one of the `targets` at the end of the chain.
+    pub fn invert_intervals(&self, targets: &[Range<usize>]) -> Vec<Range<usize>> {
+        self.maps
+            .iter()
+            .rev()
+            .fold(targets.to_vec(), |targets, map| {
+                map.invert_intervals(&targets)
+            })
     }
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
-struct Input {
+pub struct Input {
     seeds: Vec<usize>,
     maps: Vec<ConversionMap>,
 }
 
 impl Input {
-    pub fn convert(&self, num: usize) -> usize {
-        let mut res = num;
+    /// Finds the unique chain of maps that converts `from` into `to`.
+    pub fn route(&self, from: Ingredients, to: Ingredients) -> Result<Route<'_>, RouteError> {
+        let mut routes = vec![];
+        self.find_routes(from, to, &mut vec![from], &mut vec![], &mut routes);
+
+        match routes.len() {
+            0 => Err(RouteError::Broken {
+                from,
+                to,
+                reachable: self.reachable(from),
+            }),
+            1 => Ok(Route {
+                from,
+                maps: routes
+                    .remove(0)
+                    .into_iter()
+                    .map(|i| &self.maps[i])
+                    .collect(),
+            }),
+            _ => Err(RouteError::Ambiguous {
+                from,
+                to,
+                routes: routes
+                    .into_iter()
+                    .map(|route| {
+                        let mut path = vec![from];
+                        path.extend(route.into_iter().map(|i| self.maps[i].to));
+                        path
+                    })
+                    .collect(),
+            }),
+        }
+    }
+
+    // collects at most two routes, which is enough to tell unique from ambiguous
+    fn find_routes(
+        &self,
+        current: Ingredients,
+        to: Ingredients,
+        visited: &mut Vec<Ingredients>,
+        path: &mut Vec<usize>,
+        routes: &mut Vec<Vec<usize>>,
+    ) {
+        if current == to {
+            routes.push(path.clone());
+            return;
+        }
+
+        for (i, map) in self.maps.iter().enumerate() {
+            if routes.len() > 1 {
+                return;
+            }
+            if map.from != current || visited.contains(&map.to) {
+                continue;
+            }
+
+            visited.push(map.to);
+            path.push(i);
+            self.find_routes(map.to, to, visited, path, routes);
+            path.pop();
+            visited.pop();
+        }
+    }
+
+    fn reachable(&self, from: Ingredients) -> Vec<Ingredients> {
+        let mut reachable = vec![];
+        let mut pending = vec![from];
+
+        while let Some(current) = pending.pop() {
+            for map in self.maps.iter().filter(|map| map.from == current) {
+                if map.to != from && !reachable.contains(&map.to) {
+                    reachable.push(map.to);
+                    pending.push(map.to);
+                }
+            }
+        }
+
+        reachable
+    }
+
+    pub fn convert(
+        &self,
+        from: Ingredients,
+        to: Ingredients,
+        num: usize,
+    ) -> Result<usize, RouteError> {
+        Ok(self.route(from, to)?.convert(num))
+    }
+
+    pub fn convert_intervals(
+        &self,
+        from: Ingredients,
+        to: Ingredients,
+        intervals: &[Range<usize>],
+    ) -> Result<Vec<Range<usize>>, RouteError> {
+        Ok(self.route(from, to)?.convert_intervals(intervals))
+    }
+
+    /// Composes the chain of maps from `from` to `to` into a single map.
+    pub fn compose(&self, from: Ingredients, to: Ingredients) -> Result<PiecewiseMap, RouteError> {
+        Ok(self.route(from, to)?.compose())
+    }
+
+    /// Every `from` number that ends up at `num` once converted to `to`.
+    pub fn invert(
+        &self,
+        from: Ingredients,
+        to: Ingredients,
+        num: usize,
+    ) -> Result<Vec<usize>, RouteError> {
+        Ok(self.route(from, to)?.invert(num))
+    }
+
+    /// Every `from` interval that ends up inside one of the `targets` once converted to `to`.
+    pub fn invert_intervals(
+        &self,
+        from: Ingredients,
+        to: Ingredients,
+        targets: &[Range<usize>],
+    ) -> Result<Vec<Range<usize>>, RouteError> {
+        Ok(self.route(from, to)?.invert_intervals(targets))
+    }
+
+    /// Finds the lowest location reachable from the `seeds` intervals by inverting
+    /// location windows of doubling size, starting at 0, until one of them
+    /// leads back to a seed.
+    pub fn lowest_location_by_reverse(
+        &self,
+        seeds: &[Range<usize>],
+    ) -> Result<Option<usize>, RouteError> {
+        let composed = self.compose(Ingredients::Seed, Ingredients::Location)?;
+
+        let mut window = 0..1;
+        loop {
+            let lowest = composed
+                .invert_intervals(&[window.clone()])
+                .iter()
+                .flat_map(|source| seeds.iter().filter_map(|seed| intersect(source, seed)))
+                .map(|hit| composed.convert(hit.start))
+                .min();
+
+            if lowest.is_some() || window.end == usize::MAX {
+                return Ok(lowest);
+            }
+
+            window = window.end..window.end.saturating_mul(2);
+        }
+    }
+
+    /// The seed line read as (start, length) pairs, as part 2 wants it.
+    pub fn seed_intervals(&self) -> Result<Vec<Range<usize>>, SeedError> {
+        self.seeds
+            .chunks(2)
+            .map(|pair| match *pair {
+                [start, length] => match start.checked_add(length) {
+                    Some(end) => Ok(start..end),
+                    None => Err(SeedError::Overflow { start, length }),
+                },
+                [start] => Err(SeedError::MissingLength { start }),
+                _ => unreachable!("chunks of two"),
+            })
+            .collect()
+    }
+}
+
+impl Display for ConversionMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-to-{} map:", self.from, self.to)?;
+        for range in &self.ranges {
+            write!(f, "\n{range}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Input {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let seeds = self.seeds.iter().map(usize::to_string).collect::<Vec<_>>();
+        write!(f, "seeds: {}", seeds.join(" "))?;
         for map in &self.maps {
-            res = map.convert(res);
+            write!(f, "\n\n{map}")?;
         }
-        res
+        Ok(())
     }
 }
 
+fn parse_map_header(line: &str) -> Result<ConversionMap, ParseError> {
+    let name = line.split_whitespace().next().unwrap_or(line);
+
+    let segments = name.split('-').collect::<Vec<_>>();
+    let [from, "to", to] = segments[..] else {
+        return Err(ParseError::at(line, name, "a map name like `seed-to-soil`"));
+    };
+
+    let from = from
+        .parse::<Ingredients>()
+        .map_err(|err| err.within(line, from))?;
+    let to = to
+        .parse::<Ingredients>()
+        .map_err(|err| err.within(line, to))?;
+
+    Ok(ConversionMap {
+        from,
+        to,
+        ranges: vec![],
+        index: vec![],
+    })
+}
+
+/// Validates the ranges collected for `map`, which were read from `lines`.
+fn finish_map(map: ConversionMap, lines: &[(usize, &str)]) -> Result<ConversionMap, ParseError> {
+    ConversionMap::new(map.from, map.to, map.ranges).map_err(|err| {
+        let (first, _) = lines[err.first];
+        let (line_number, line) = lines[err.second];
+        let src_start = line.split(' ').nth(1).unwrap_or(line);
+
+        ParseError::at(
+            line,
+            src_start,
+            format!("a source range that does not overlap the one on line {first}"),
+        )
+        .on_line(line_number, line)
+    })
+}
+
 impl FromStr for Input {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut lines = s.lines();
+        let mut lines = s.lines().enumerate().map(|(i, line)| (i + 1, line));
 
-        let seed_line = lines.next().expect("invalid input");
+        let Some((_, seed_line)) = lines.next() else {
+            return Err(ParseError::at_end(s, "a line listing the seeds"));
+        };
 
-        let seeds = seed_line
+        let nums = seed_line
             .trim_start_matches("seeds: ")
             .split_whitespace()
-            .map(|num| num.parse::<usize>().expect("invalid input"))
             .collect::<Vec<_>>();
+        let seeds = nums
+            .iter()
+            .map(|num| {
+                num.parse::<usize>().map_err(|_| {
+                    ParseError::at(seed_line, num, "a seed number").on_line(1, seed_line)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
         let mut input = Input {
             seeds,
             maps: vec![],
         };
 
-        let mut map: Option<ConversionMap> = None;
+        // the header of the current map and its ranges with the lines they come from
+        let mut map: Option<(ConversionMap, Vec<(usize, &str)>)> = None;
 
-        for line in lines {
+        for (line_number, line) in lines {
             if line.starts_with(|c: char| c.is_alphabetic()) {
-                let mut segments = line.split_whitespace();
-                let segments = segments
-                    .next()
-                    .expect("invalid input")
-                    .split('-')
-                    .collect::<Vec<_>>();
-
-                let from = segments[0].parse::<Ingredients>().expect("invalid input");
-                let to = segments[2].parse::<Ingredients>().expect("invalid input");
-
-                map = Some(ConversionMap {
-                    from,
-                    to,
-                    ranges: vec![],
-                });
+                if let Some((inner, lines)) = map.take() {
+                    input.maps.push(finish_map(inner, &lines)?);
+                }
+                let header =
+                    parse_map_header(line).map_err(|err| err.on_line(line_number, line))?;
+                map = Some((header, vec![]));
             } else if line.trim().is_empty() {
-                if let Some(inner) = map {
-                    input.maps.push(inner);
-                    map = None;
+                if let Some((inner, lines)) = map.take() {
+                    input.maps.push(finish_map(inner, &lines)?);
                 };
             } else {
-                let range = line.parse::<ConversionRange>().expect("invalid input");
+                let range = line
+                    .parse::<ConversionRange>()
+                    .map_err(|err| err.on_line(line_number, line))?;
                 match map.as_mut() {
-                    Some(inner) => inner.append(range),
-                    None => unreachable!(),
+                    Some((inner, lines)) => {
+                        inner.ranges.push(range);
+                        lines.push((line_number, line));
+                    }
+                    None => {
+                        return Err(ParseError::at(line, line, "a map header before its ranges")
+                            .on_line(line_number, line))
+                    }
                 }
             }
         }
 
-        if let Some(inner) = map {
-            input.maps.push(inner);
+        if let Some((inner, lines)) = map {
+            input.maps.push(finish_map(inner, &lines)?);
         };
 
         Ok(input)
@@ -166,57 +899,43 @@ impl FromStr for Input {
 }
 
 #[aoc_generator(day05)]
-fn generator_aoc_day_5(input: &str) -> Input {
-    input.parse().expect("invalid input")
+fn generator_aoc_day_5(input: &str) -> Result<Input, ParseError> {
+    input.parse().map_err(|err: ParseError| err.in_day(5))
 }
 
 #[aoc(day05, part1)]
 fn day05_part_1(input: &Input) -> usize {
+    let route = input
+        .route(Ingredients::Seed, Ingredients::Location)
+        .unwrap_or_else(|err| panic!("{err}"));
+
     input
         .seeds
         .iter()
-        .map(|seed| input.convert(*seed))
+        .map(|seed| route.convert(*seed))
         .reduce(|memo, cur| memo.min(cur))
         .expect("invalid input")
 }
 
 #[aoc(day05, part2)]
 fn day05_part_2(input: &Input) -> usize {
-    let seeds = input.seeds.clone();
-
-    let mut threads = vec![];
-
-    let num = seeds.len() / 2;
-
-    for i in 0..num {
-        let start = seeds[i * 2];
-        let len = seeds[i * 2 + 1];
-
-        let input = input.clone();
+    let seeds = input.seed_intervals().unwrap_or_else(|err| panic!("{err}"));
 
-        threads.push(thread::spawn(move || {
-            let mut small = usize::MAX;
-            for j in start..start + len {
-                small = small.min(input.convert(j));
-            }
-            small
-        }));
-    }
-
-    let mut small = usize::MAX;
-
-    for ele in threads {
-        let res = ele.join().unwrap();
-        small = small.min(res);
-    }
-
-    small
+    input
+        .convert_intervals(Ingredients::Seed, Ingredients::Location, &seeds)
+        .unwrap_or_else(|err| panic!("{err}"))
+        .iter()
+        .map(|interval| interval.start)
+        .min()
+        .expect("invalid input")
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use crate::test_rng::Rng;
+    use Ingredients::*;
 
     const INPUT: &str = "seeds: 79 14 55 13
 
@@ -257,10 +976,10 @@ humidity-to-location map:
             Input {
                 seeds: vec![79, 14, 55, 13],
                 maps: vec![
-                    ConversionMap {
-                        from: Ingredients::Seed,
-                        to: Ingredients::Soil,
-                        ranges: vec![
+                    ConversionMap::new(
+                        Ingredients::Seed,
+                        Ingredients::Soil,
+                        vec![
                             ConversionRange {
                                 src_start: 98,
                                 dest_start: 50,
@@ -272,11 +991,12 @@ humidity-to-location map:
                                 length: 48,
                             },
                         ],
-                    },
-                    ConversionMap {
-                        from: Ingredients::Soil,
-                        to: Ingredients::Fertilizer,
-                        ranges: vec![
+                    )
+                    .unwrap(),
+                    ConversionMap::new(
+                        Ingredients::Soil,
+                        Ingredients::Fertilizer,
+                        vec![
                             ConversionRange {
                                 src_start: 15,
                                 dest_start: 0,
@@ -293,11 +1013,12 @@ humidity-to-location map:
                                 length: 15,
                             },
                         ],
-                    },
-                    ConversionMap {
-                        from: Ingredients::Fertilizer,
-                        to: Ingredients::Water,
-                        ranges: vec![
+                    )
+                    .unwrap(),
+                    ConversionMap::new(
+                        Ingredients::Fertilizer,
+                        Ingredients::Water,
+                        vec![
                             ConversionRange {
                                 src_start: 53,
                                 dest_start: 49,
@@ -319,11 +1040,12 @@ humidity-to-location map:
                                 length: 4,
                             },
                         ],
-                    },
-                    ConversionMap {
-                        from: Ingredients::Water,
-                        to: Ingredients::Light,
-                        ranges: vec![
+                    )
+                    .unwrap(),
+                    ConversionMap::new(
+                        Ingredients::Water,
+                        Ingredients::Light,
+                        vec![
                             ConversionRange {
                                 src_start: 18,
                                 dest_start: 88,
@@ -335,11 +1057,12 @@ humidity-to-location map:
                                 length: 70,
                             },
                         ],
-                    },
-                    ConversionMap {
-                        from: Ingredients::Light,
-                        to: Ingredients::Temperature,
-                        ranges: vec![
+                    )
+                    .unwrap(),
+                    ConversionMap::new(
+                        Ingredients::Light,
+                        Ingredients::Temperature,
+                        vec![
                             ConversionRange {
                                 src_start: 77,
                                 dest_start: 45,
@@ -356,11 +1079,12 @@ humidity-to-location map:
                                 length: 13,
                             },
                         ],
-                    },
-                    ConversionMap {
-                        from: Ingredients::Temperature,
-                        to: Ingredients::Humidity,
-                        ranges: vec![
+                    )
+                    .unwrap(),
+                    ConversionMap::new(
+                        Ingredients::Temperature,
+                        Ingredients::Humidity,
+                        vec![
                             ConversionRange {
                                 src_start: 69,
                                 dest_start: 0,
@@ -372,11 +1096,12 @@ humidity-to-location map:
                                 length: 69,
                             },
                         ],
-                    },
-                    ConversionMap {
-                        from: Ingredients::Humidity,
-                        to: Ingredients::Location,
-                        ranges: vec![
+                    )
+                    .unwrap(),
+                    ConversionMap::new(
+                        Ingredients::Humidity,
+                        Ingredients::Location,
+                        vec![
                             ConversionRange {
                                 src_start: 56,
                                 dest_start: 60,
@@ -388,7 +1113,8 @@ humidity-to-location map:
                                 length: 4,
                             },
                         ],
-                    },
+                    )
+                    .unwrap(),
                 ],
             }
         };
@@ -423,6 +1149,64 @@ humidity-to-location map:
         )
     }
 
+    #[test]
+    fn test_conversion_range_parse_errors() {
+        let err = ConversionRange::from_str("17 13x7 42").unwrap_err();
+        assert_eq!(err.span(), 3..7);
+        assert_eq!(err.expected(), "a source range start");
+
+        let err = ConversionRange::from_str("17 1337").unwrap_err();
+        assert_eq!(err.span(), 7..7);
+        assert_eq!(err.expected(), "a range length");
+    }
+
+    #[test]
+    fn test_input_parse_errors() {
+        let err = generator_aoc_day_5("seeds: 79 14\n\nseed-to-sole map:\n50 98 2").unwrap_err();
+        assert_eq!(err.day(), Some(5));
+        assert_eq!(err.line(), Some(3));
+        assert_eq!(err.span(), 8..12);
+
+        let err = generator_aoc_day_5("seeds: 79 14\n\nseed-soil map:\n50 98 2").unwrap_err();
+        assert_eq!(err.line(), Some(3));
+        assert_eq!(err.span(), 0..9);
+
+        let err = generator_aoc_day_5("seeds: 79 14\n\n50 98 2").unwrap_err();
+        assert_eq!(err.line(), Some(3));
+        assert_eq!(err.expected(), "a map header before its ranges");
+
+        let err = generator_aoc_day_5("seeds: 79 x4").unwrap_err();
+        assert_eq!(err.line(), Some(1));
+        assert_eq!(err.span(), 10..12);
+    }
+
+    #[test]
+    fn test_seed_intervals() {
+        let input = gen_input!();
+        assert_eq!(input.seed_intervals(), Ok(vec![79..93, 55..68]));
+
+        // part 1 takes any seeds, only part 2 needs them in pairs
+        let input = generator_aoc_day_5(&INPUT.replacen("79 14 55 13", "79 14 55", 1)).unwrap();
+        assert_eq!(day05_part_1(&input), 43);
+        let err = input.seed_intervals().unwrap_err();
+        assert_eq!(err, SeedError::MissingLength { start: 55 });
+        assert_eq!(
+            err.to_string(),
+            "the seed range starting at 55 has no length"
+        );
+
+        let seeds = format!("79 14 {} 2", usize::MAX - 1);
+        let input = generator_aoc_day_5(&INPUT.replacen("79 14 55 13", &seeds, 1)).unwrap();
+        assert_eq!(day05_part_1(&input), 24);
+        assert_eq!(
+            input.seed_intervals(),
+            Err(SeedError::Overflow {
+                start: usize::MAX - 1,
+                length: 2
+            })
+        );
+    }
+
     #[test]
     fn test_input_parse() {
         let input = Input::from_str(INPUT);
@@ -435,10 +1219,19 @@ humidity-to-location map:
     fn test_input_convert() {
         let input = gen_input!();
 
-        assert_eq!(input.convert(79), 82);
-        assert_eq!(input.convert(14), 43);
-        assert_eq!(input.convert(55), 86);
-        assert_eq!(input.convert(13), 35);
+        assert_eq!(input.convert(Seed, Location, 79).unwrap(), 82);
+        assert_eq!(input.convert(Seed, Location, 14).unwrap(), 43);
+        assert_eq!(input.convert(Seed, Location, 55).unwrap(), 86);
+        assert_eq!(input.convert(Seed, Location, 13).unwrap(), 35);
+
+        let route = input.route(Seed, Location).unwrap();
+        assert_eq!(route.maps().len(), 7);
+        for seed in [79, 14, 55, 13] {
+            assert_eq!(
+                route.convert(seed),
+                input.convert(Seed, Location, seed).unwrap()
+            );
+        }
     }
 
     #[test]
@@ -454,4 +1247,422 @@ humidity-to-location map:
 
         assert_eq!(day05_part_2(&input), 46)
     }
+
+    #[test]
+    #[allow(clippy::single_range_in_vec_init)]
+    fn test_conversion_range_convert_interval() {
+        let range = ConversionRange {
+            src_start: 100,
+            dest_start: 42,
+            length: 69,
+        };
+
+        assert_eq!(range.convert_interval(0..50), (None, vec![0..50]));
+        assert_eq!(range.convert_interval(169..200), (None, vec![169..200]));
+        assert_eq!(range.convert_interval(100..169), (Some(42..111), vec![]));
+        assert_eq!(
+            range.convert_interval(90..110),
+            (Some(42..52), vec![90..100])
+        );
+        assert_eq!(
+            range.convert_interval(150..180),
+            (Some(92..111), vec![169..180])
+        );
+        assert_eq!(
+            range.convert_interval(0..200),
+            (Some(42..111), vec![0..100, 169..200])
+        );
+    }
+
+    #[test]
+    fn test_input_convert_intervals_matches_convert() {
+        let input = gen_input!();
+
+        let intervals = vec![79..93, 55..68, 0..120, 97..99, 5..5];
+
+        for interval in intervals {
+            let mut expected = interval
+                .clone()
+                .map(|seed| input.convert(Seed, Location, seed).unwrap())
+                .collect::<Vec<_>>();
+            expected.sort();
+
+            let mut actual = input
+                .convert_intervals(Seed, Location, &[interval])
+                .unwrap()
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>();
+            actual.sort();
+
+            assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_conversion_map_compose() {
+        let first = ConversionMap::new(
+            Ingredients::Seed,
+            Ingredients::Soil,
+            vec![ConversionRange {
+                src_start: 10,
+                dest_start: 20,
+                length: 5,
+            }],
+        )
+        .unwrap();
+        let second = ConversionMap::new(
+            Ingredients::Soil,
+            Ingredients::Fertilizer,
+            vec![ConversionRange {
+                src_start: 22,
+                dest_start: 10,
+                length: 10,
+            }],
+        )
+        .unwrap();
+
+        let composed = first.compose(&second);
+
+        assert_eq!(composed.from, Ingredients::Seed);
+        assert_eq!(composed.to, Ingredients::Fertilizer);
+        assert_eq!(
+            composed.segments(),
+            &[
+                ConversionRange {
+                    src_start: 10,
+                    dest_start: 20,
+                    length: 2,
+                },
+                ConversionRange {
+                    src_start: 12,
+                    dest_start: 10,
+                    length: 3,
+                },
+                ConversionRange {
+                    src_start: 22,
+                    dest_start: 10,
+                    length: 10,
+                },
+            ]
+        );
+
+        for n in 0..50 {
+            assert_eq!(composed.convert(n), second.convert(first.convert(n)));
+        }
+    }
+
+    #[test]
+    fn test_input_compose_matches_convert() {
+        let input = gen_input!();
+        let composed = input.compose(Seed, Location).unwrap();
+
+        assert_eq!(composed.from, Ingredients::Seed);
+        assert_eq!(composed.to, Ingredients::Location);
+
+        for n in 0..200 {
+            assert_eq!(
+                composed.convert(n),
+                input.convert(Seed, Location, n).unwrap(),
+                "seed {n}"
+            );
+        }
+
+        let breakpoints = composed.breakpoints();
+        assert!(breakpoints.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(breakpoints.first(), Some(&0));
+        assert_eq!(breakpoints.last(), Some(&100));
+    }
+
+    #[test]
+    fn test_conversion_range_invert() {
+        let range = ConversionRange {
+            src_start: 100,
+            dest_start: 42,
+            length: 69,
+        };
+
+        assert_eq!(range.invert(42), Some(100));
+        assert_eq!(range.invert(110), Some(168));
+        assert_eq!(range.invert(111), None);
+        assert_eq!(range.invert(41), None);
+    }
+
+    #[test]
+    fn test_conversion_map_invert() {
+        let input = gen_input!();
+        let seed_to_soil = &input.maps[0];
+
+        assert_eq!(seed_to_soil.invert(50), vec![98]);
+        assert_eq!(seed_to_soil.invert(52), vec![50]);
+        // 99 is reached from seed 97 only, since seed 99 itself is mapped away
+        assert_eq!(seed_to_soil.invert(99), vec![97]);
+        assert_eq!(seed_to_soil.invert(10), vec![10]);
+    }
+
+    #[test]
+    #[allow(clippy::single_range_in_vec_init)]
+    fn test_input_invert_matches_convert() {
+        let input = gen_input!();
+
+        for location in 0..120 {
+            let mut expected = (0..200)
+                .filter(|seed| input.convert(Seed, Location, *seed).unwrap() == location)
+                .collect::<Vec<_>>();
+            expected.sort();
+
+            let actual = input
+                .invert(Seed, Location, location)
+                .unwrap()
+                .into_iter()
+                .filter(|seed| *seed < 200)
+                .collect::<Vec<_>>();
+
+            let mut from_intervals = input
+                .invert_intervals(Seed, Location, &[location..location + 1])
+                .unwrap()
+                .into_iter()
+                .flatten()
+                .filter(|seed| *seed < 200)
+                .collect::<Vec<_>>();
+            from_intervals.sort();
+
+            assert_eq!(actual, expected, "location {location}");
+            assert_eq!(from_intervals, expected, "location {location}");
+        }
+
+        assert!(input.invert(Seed, Location, 46).unwrap().contains(&82));
+    }
+
+    #[test]
+    fn test_lowest_location_by_reverse() {
+        let input = gen_input!();
+
+        let seeds = input.seed_intervals().unwrap();
+
+        assert_eq!(input.lowest_location_by_reverse(&seeds), Ok(Some(46)));
+        assert_eq!(
+            input.lowest_location_by_reverse(&seeds),
+            Ok(Some(day05_part_2(&input)))
+        );
+    }
+
+    #[test]
+    fn test_input_convert_between_categories() {
+        let input = gen_input!();
+
+        for n in 0..120 {
+            let expected = input.maps[1..6].iter().fold(n, |num, map| map.convert(num));
+            assert_eq!(input.convert(Soil, Humidity, n), Ok(expected));
+        }
+
+        assert_eq!(input.convert(Water, Water, 42), Ok(42));
+    }
+
+    #[test]
+    fn test_input_convert_shuffled_maps() {
+        let input = gen_input!();
+        let mut shuffled = input.clone();
+        shuffled.maps.reverse();
+        shuffled.maps.swap(1, 4);
+
+        for n in 0..120 {
+            assert_eq!(
+                shuffled.convert(Seed, Location, n),
+                input.convert(Seed, Location, n)
+            );
+        }
+        assert_eq!(day05_part_1(&shuffled), 35);
+        assert_eq!(day05_part_2(&shuffled), 46);
+    }
+
+    #[test]
+    fn test_input_route_broken() {
+        let mut input = gen_input!();
+        input.maps.remove(3);
+
+        let err = input.route(Seed, Location).unwrap_err();
+        assert_eq!(
+            err,
+            RouteError::Broken {
+                from: Seed,
+                to: Location,
+                reachable: vec![Soil, Fertilizer, Water],
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "no chain of maps converts seed to location (only reaches soil, fertilizer, water)"
+        );
+        assert_eq!(
+            input.route(Location, Seed).unwrap_err().to_string(),
+            "no chain of maps converts location to seed (no map starts at location)"
+        );
+    }
+
+    #[test]
+    fn test_input_route_ambiguous() {
+        let mut input = gen_input!();
+        input
+            .maps
+            .push(ConversionMap::new(Soil, Water, vec![]).unwrap());
+
+        let err = input.route(Seed, Location).unwrap_err();
+        assert_eq!(
+            err,
+            RouteError::Ambiguous {
+                from: Seed,
+                to: Location,
+                routes: vec![
+                    vec![
+                        Seed,
+                        Soil,
+                        Fertilizer,
+                        Water,
+                        Light,
+                        Temperature,
+                        Humidity,
+                        Location
+                    ],
+                    vec![Seed, Soil, Water, Light, Temperature, Humidity, Location],
+                ],
+            }
+        );
+        assert!(err
+            .to_string()
+            .contains("seed -> soil -> water -> light -> temperature -> humidity -> location"));
+
+        // the detour doesn't matter for conversions that don't pass through it
+        assert!(input.route(Water, Location).is_ok());
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let mut rng = Rng::new(5);
+        let categories = [
+            Seed,
+            Soil,
+            Fertilizer,
+            Water,
+            Light,
+            Temperature,
+            Humidity,
+            Location,
+        ];
+
+        for _ in 0..100 {
+            let input = Input {
+                seeds: rng
+                    .vec(0, 4, |rng| {
+                        let start = rng.below(1 << 62) as usize;
+                        [start, rng.below(1 << 62) as usize]
+                    })
+                    .concat(),
+                maps: rng.vec(0, 5, |rng| {
+                    // disjoint ranges, some of them empty, in any order
+                    let mut src_start = 0;
+                    let mut ranges = rng.vec(0, 6, |rng| {
+                        src_start += rng.below(1 << 40) as usize;
+                        let range = ConversionRange {
+                            src_start,
+                            dest_start: rng.below(1 << 62) as usize,
+                            length: (rng.below(4) * rng.below(1000)) as usize,
+                        };
+                        src_start += range.length;
+                        range
+                    });
+                    for i in (1..ranges.len()).rev() {
+                        ranges.swap(i, rng.below(i as u64 + 1) as usize);
+                    }
+
+                    ConversionMap::new(*rng.pick(&categories), *rng.pick(&categories), ranges)
+                        .unwrap()
+                }),
+            };
+
+            assert_eq!(Input::from_str(&input.to_string()), Ok(input));
+        }
+
+        assert_eq!(gen_input!().to_string(), INPUT);
+    }
+
+    #[test]
+    fn test_conversion_map_validation() {
+        let range = |src_start, length| ConversionRange {
+            src_start,
+            dest_start: 1000,
+            length,
+        };
+
+        let map = ConversionMap::new(Seed, Soil, vec![range(50, 10), range(0, 50), range(60, 0)])
+            .unwrap();
+        assert_eq!(map.ranges(), &[range(50, 10), range(0, 50), range(60, 0)]);
+        assert_eq!(map.convert(49), 1049);
+        assert_eq!(map.convert(50), 1000);
+        assert_eq!(map.convert(60), 60);
+
+        let err = ConversionMap::new(Seed, Soil, vec![range(10, 5), range(0, 10), range(5, 20)])
+            .unwrap_err();
+        assert_eq!((err.first, err.second), (1, 2));
+        assert_eq!(
+            err.to_string(),
+            "ranges 2 and 3 of the seed-to-soil map overlap"
+        );
+
+        // empty ranges convert nothing and never get in the way of the others
+        let map = ConversionMap::new(Seed, Soil, vec![range(0, 10), range(5, 0)]).unwrap();
+        assert_eq!(map.ranges(), &[range(0, 10), range(5, 0)]);
+        assert_eq!(map.convert(5), 1005);
+        let map =
+            ConversionMap::new(Seed, Soil, vec![range(5, 0), range(5, 10), range(15, 0)]).unwrap();
+        assert_eq!(map.convert(5), 1000);
+        assert_eq!(map.convert(15), 15);
+    }
+
+    #[test]
+    fn test_conversion_map_binary_search() {
+        let mut rng = Rng::new(25);
+
+        for _ in 0..50 {
+            let mut src_start = 0;
+            let ranges = rng.vec(0, 20, |rng| {
+                src_start += rng.below(20) as usize;
+                let range = ConversionRange {
+                    src_start,
+                    dest_start: rng.below(1000) as usize,
+                    length: rng.below(20) as usize,
+                };
+                src_start += range.length;
+                range
+            });
+            let map =
+                ConversionMap::new(Seed, Soil, ranges.iter().rev().cloned().collect()).unwrap();
+
+            for num in 0..src_start + 10 {
+                let expected = ranges
+                    .iter()
+                    .find_map(|range| range.convert(num))
+                    .unwrap_or(num);
+                assert_eq!(map.convert(num), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_overlap_and_overflow_errors() {
+        let err =
+            Input::from_str("seeds: 1 1\n\nseed-to-soil map:\n0 10 5\n0 0 5\n7 12 1").unwrap_err();
+        assert_eq!(err.line(), Some(6));
+        assert_eq!(err.span(), 2..4);
+        assert_eq!(
+            err.expected(),
+            "a source range that does not overlap the one on line 4"
+        );
+
+        let err = ConversionRange::from_str(&format!("0 {} 2", usize::MAX - 1)).unwrap_err();
+        assert_eq!(err.expected(), "a range length that does not overflow");
+        assert_eq!(err.span(), 23..24);
+        assert!(ConversionRange::from_str(&format!("0 {} 1", usize::MAX - 1)).is_ok());
+        assert!(ConversionRange::from_str(&format!("{} 0 2", usize::MAX)).is_err());
+    }
 }
@@ -1,152 +1,381 @@
+use std::fmt::Display;
+
 use aoc_runner_derive::{aoc, aoc_generator};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct Race {
-    time: u64,
-    distance: u64,
+use crate::{big_uint::BigUint, parse_error::ParseError};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Race {
+    time: BigUint,
+    distance: BigUint,
 }
 
-#[aoc_generator(day06, part1)]
-fn generator_day06_part1(input: &str) -> Vec<Race> {
-    let lines = input.lines().collect::<Vec<_>>();
+fn wins(time: u64, distance: u64, hold: u64) -> bool {
+    match (time - hold).checked_mul(hold) {
+        Some(travelled) => travelled > distance,
+        None => (time - hold) as u128 * hold as u128 > distance as u128,
+    }
+}
 
-    let mut races = vec![];
+/// Counts the hold times `x` in `0..=time` with `(time - x) * x > distance`.
+///
+/// The winning holds lie strictly between the roots of `x^2 - time * x + distance`.
+/// An integer square root of the discriminant gets within one of the lower root,
+/// which is then nudged onto the exact boundary; the upper one follows by symmetry.
+fn winning_holds_u64(time: u64, distance: u64) -> u64 {
+    let discriminant = match (time.checked_mul(time), distance.checked_mul(4)) {
+        (Some(square), Some(four_distance)) => square.checked_sub(four_distance).map(u128::from),
+        _ => (time as u128 * time as u128).checked_sub(4 * distance as u128),
+    };
+    let discriminant = match discriminant {
+        Some(discriminant) if discriminant > 0 => discriminant,
+        _ => return 0,
+    };
+
+    let mut lowest = ((time as u128 - discriminant.isqrt()) / 2) as u64;
+    while lowest > 0 && wins(time, distance, lowest - 1) {
+        lowest -= 1;
+    }
+    while lowest <= time / 2 && !wins(time, distance, lowest) {
+        lowest += 1;
+    }
 
-    let times = lines[0]
-        .trim_start_matches("Time:")
-        .split_whitespace()
-        .map(|n| n.parse::<u64>().unwrap())
-        .collect::<Vec<_>>();
+    if lowest > time / 2 {
+        return 0;
+    }
 
-    let distances = lines[1]
-        .trim_start_matches("Distance:")
-        .split_whitespace()
-        .map(|n| n.parse::<u64>().unwrap())
-        .collect::<Vec<_>>();
+    time - 2 * lowest + 1
+}
 
-    assert_eq!(times.len(), distances.len());
+/// Same as [`winning_holds_u64`], for races that don't fit into 64 bits.
+fn winning_holds_big(time: &BigUint, distance: &BigUint) -> BigUint {
+    let one = BigUint::from(1);
+    let two = BigUint::from(2);
+    let half = time.div_rem_small(2).0;
+    let wins = |hold: &BigUint| &(&(time - hold) * hold) > distance;
+
+    let discriminant = match (time * time).checked_sub(&(&BigUint::from(4) * distance)) {
+        Some(discriminant) if !discriminant.is_zero() => discriminant,
+        _ => return BigUint::default(),
+    };
+
+    let mut lowest = (time - &discriminant.isqrt()).div_rem_small(2).0;
+    while !lowest.is_zero() && wins(&(&lowest - &one)) {
+        lowest = &lowest - &one;
+    }
+    while lowest <= half && !wins(&lowest) {
+        lowest = &lowest + &one;
+    }
 
-    for (i, time) in times.into_iter().enumerate() {
-        races.push(Race {
-            time,
-            distance: distances[i],
-        })
+    if lowest > half {
+        return BigUint::default();
     }
 
-    races
+    &(time - &(&two * &lowest)) + &one
 }
 
-#[aoc(day06, part1)]
-fn day_06_part_1(input: &[Race]) -> u64 {
-    let mut res = vec![];
+impl Race {
+    /// Counts the winning hold times, in 64 bit arithmetic (widened to 128 bits
+    /// where it would overflow) if the race fits, and in big integers otherwise.
+    pub fn winning_holds(&self) -> BigUint {
+        match (self.time.to_u64(), self.distance.to_u64()) {
+            (Some(time), Some(distance)) => BigUint::from(winning_holds_u64(time, distance)),
+            _ => winning_holds_big(&self.time, &self.distance),
+        }
+    }
+}
 
-    for race in input {
-        let func = |x: u64| (race.time - x) * x > race.distance;
+/// Prints a single race as a document of its own.
+impl Display for Race {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        format_races(std::slice::from_ref(self)).fmt(f)
+    }
+}
 
-        let mut counter = 0;
+/// Prints races the way the puzzle does, with the numbers of every race right-aligned
+/// in a column of their own.
+pub fn format_races(races: &[Race]) -> String {
+    let mut times = String::from("Time:    ");
+    let mut distances = String::from("Distance:");
+
+    for Race { time, distance } in races {
+        let (time, distance) = (time.to_string(), distance.to_string());
+        let width = time.len().max(distance.len());
+        times.push_str(&format!("  {time:>width$}"));
+        distances.push_str(&format!("  {distance:>width$}"));
+    }
 
-        for i in 0..race.time + 1 {
-            if func(i) {
-                counter += 1;
-            }
-        }
+    format!("{times}\n{distances}")
+}
 
-        res.push(counter)
+/// Returns the numbers of the line at `index`, which has to start with `label`.
+fn parse_race_line<'a>(
+    lines: &[&'a str],
+    index: usize,
+    label: &str,
+) -> Result<(&'a str, Vec<&'a str>), ParseError> {
+    let Some(line) = lines.get(index) else {
+        return Err(ParseError::new(0..0, format!("a `{label}` line"))
+            .on_line(index + 1, "")
+            .in_day(6));
+    };
+
+    let Some(rest) = line.strip_prefix(label) else {
+        return Err(
+            ParseError::at(line, line, format!("a line starting with `{label}`"))
+                .on_line(index + 1, line)
+                .in_day(6),
+        );
+    };
+
+    let numbers = rest.split_whitespace().collect::<Vec<_>>();
+    if let Some(invalid) = numbers
+        .iter()
+        .find(|n| !n.chars().all(|c| c.is_ascii_digit()))
+    {
+        return Err(ParseError::at(line, invalid, "a number")
+            .on_line(index + 1, line)
+            .in_day(6));
     }
 
-    res.into_iter().reduce(|memo, cur| memo * cur).unwrap()
+    Ok((line, numbers))
 }
 
-#[aoc_generator(day06, part2)]
-fn generator_day06_part2(input: &str) -> Race {
+#[aoc_generator(day06, part1)]
+fn generator_day06_part1(input: &str) -> Result<Vec<Race>, ParseError> {
     let lines = input.lines().collect::<Vec<_>>();
 
-    let time = lines[0]
-        .trim_start_matches("Time:")
-        .split_whitespace()
-        .collect::<String>()
-        .parse::<u64>()
-        .unwrap();
-
-    let distance = lines[1]
-        .trim_start_matches("Distance:")
-        .split_whitespace()
-        .collect::<String>()
-        .parse::<u64>()
-        .unwrap();
-
-    Race { time, distance }
+    let (_, times) = parse_race_line(&lines, 0, "Time:")?;
+    let (distance_line, distances) = parse_race_line(&lines, 1, "Distance:")?;
+
+    if times.len() != distances.len() {
+        return Err(ParseError::at(
+            distance_line,
+            distance_line,
+            format!("as many distances as times ({})", times.len()),
+        )
+        .on_line(2, distance_line)
+        .in_day(6));
+    }
+
+    let parse = |n: &str| n.parse::<BigUint>().expect("validated digits");
+
+    Ok(times
+        .into_iter()
+        .zip(distances)
+        .map(|(time, distance)| Race {
+            time: parse(time),
+            distance: parse(distance),
+        })
+        .collect())
 }
 
-#[aoc(day06, part2)]
-fn day_06_part_2(Race { time, distance }: &Race) -> u64 {
-    let func = |x: u64| (*time - x) * x > *distance;
+#[aoc(day06, part1)]
+fn day_06_part_1(input: &[Race]) -> BigUint {
+    input.iter().map(Race::winning_holds).product()
+}
 
-    let mut counter = 0;
+#[aoc_generator(day06, part2)]
+fn generator_day06_part2(input: &str) -> Result<Race, ParseError> {
+    let lines = input.lines().collect::<Vec<_>>();
 
-    for i in 0..time + 1 {
-        if func(i) {
-            counter += 1;
-        }
-    }
+    let kerned = |index: usize, label: &str| -> Result<BigUint, ParseError> {
+        let (line, numbers) = parse_race_line(&lines, index, label)?;
+        numbers.concat().parse::<BigUint>().map_err(|_| {
+            ParseError::at_end(line, "at least one number")
+                .on_line(index + 1, line)
+                .in_day(6)
+        })
+    };
 
-    counter
+    Ok(Race {
+        time: kerned(0, "Time:")?,
+        distance: kerned(1, "Distance:")?,
+    })
+}
+
+#[aoc(day06, part2)]
+fn day_06_part_2(race: &Race) -> BigUint {
+    race.winning_holds()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_rng::Rng;
+
+    fn race(time: u64, distance: u64) -> Race {
+        Race {
+            time: BigUint::from(time),
+            distance: BigUint::from(distance),
+        }
+    }
+
+    fn winning_holds_by_loop(time: u64, distance: u64) -> u64 {
+        let func = |x: u64| (time - x) * x > distance;
+
+        let mut counter = 0;
+
+        for i in 0..time + 1 {
+            if func(i) {
+                counter += 1;
+            }
+        }
+
+        counter
+    }
 
     const INPUT: &str = "Time:      7  15   30
 Distance:  9  40  200";
 
     #[test]
     fn test_generator_day06_part1() {
-        let gen = generator_day06_part1(INPUT);
+        let gen = generator_day06_part1(INPUT).unwrap();
 
-        assert_eq!(
-            gen,
-            vec![
-                Race {
-                    time: 7,
-                    distance: 9
-                },
-                Race {
-                    time: 15,
-                    distance: 40
-                },
-                Race {
-                    time: 30,
-                    distance: 200
-                }
-            ]
-        )
+        assert_eq!(gen, vec![race(7, 9), race(15, 40), race(30, 200)])
     }
 
     #[test]
     fn test_day06_part1() {
-        let gen = generator_day06_part1(INPUT);
+        let gen = generator_day06_part1(INPUT).unwrap();
 
-        assert_eq!(day_06_part_1(&gen), 288);
+        assert_eq!(day_06_part_1(&gen), BigUint::from(288));
     }
 
     #[test]
     fn test_generator_day06_part2() {
-        let gen = generator_day06_part2(INPUT);
+        let gen = generator_day06_part2(INPUT).unwrap();
 
-        assert_eq!(
-            gen,
-            Race {
-                time: 71530,
-                distance: 940200
-            }
-        )
+        assert_eq!(gen, race(71530, 940200))
     }
 
     #[test]
     fn test_day06_part2() {
-        let gen = generator_day06_part2(INPUT);
+        let gen = generator_day06_part2(INPUT).unwrap();
+
+        assert_eq!(day_06_part_2(&gen), BigUint::from(71503));
+    }
+
+    #[test]
+    fn test_winning_holds_ties() {
+        // holding 10 or 20 exactly ties the record and must not count
+        assert_eq!(winning_holds_u64(30, 200), 9);
+        // the best possible hold only ties the record
+        assert_eq!(winning_holds_u64(10, 25), 0);
+        assert_eq!(winning_holds_u64(10, 24), 1);
+        assert_eq!(winning_holds_u64(0, 0), 0);
+    }
+
+    #[test]
+    fn test_winning_holds_matches_loop() {
+        for time in 0..120 {
+            for distance in 0..(time * time / 4 + 3) {
+                let expected = winning_holds_by_loop(time, distance);
+                assert_eq!(
+                    winning_holds_u64(time, distance),
+                    expected,
+                    "{time} {distance}"
+                );
+                assert_eq!(
+                    winning_holds_big(&BigUint::from(time), &BigUint::from(distance)),
+                    BigUint::from(expected),
+                    "{time} {distance}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_winning_holds_overflowing_u64() {
+        // (t / 2)^2 overflows u64, so this has to fall back to u128
+        let time = 10_000_000_000;
+        assert_eq!(
+            BigUint::from(winning_holds_u64(time, u64::MAX)),
+            winning_holds_big(&BigUint::from(time), &BigUint::from(u64::MAX))
+        );
+        assert_eq!(winning_holds_u64(time, 0), time - 1);
+    }
+
+    #[test]
+    fn test_winning_holds_big() {
+        let half = "500000000000000000000000000000".parse::<BigUint>().unwrap();
+        let time = &half + &half;
+        let best = &half * &half;
+
+        let race = Race {
+            time: time.clone(),
+            distance: &best - &BigUint::from(1),
+        };
+        assert_eq!(race.winning_holds(), BigUint::from(1));
+
+        let race = Race {
+            time,
+            distance: best,
+        };
+        assert_eq!(race.winning_holds(), BigUint::default());
+    }
+
+    #[test]
+    fn test_generator_day06_part2_beyond_u64() {
+        let input = "Time:      100000  00000  00000  00000  0000
+Distance:  1  0";
+
+        let gen = generator_day06_part2(input).unwrap();
+        assert_eq!(gen.time.to_string(), "1000000000000000000000000");
+        assert_eq!(gen.time.to_u64(), None);
+        assert_eq!(day_06_part_2(&gen).to_string(), "999999999999999999999999");
+    }
+
+    #[test]
+    fn test_generator_day06_errors() {
+        let err =
+            generator_day06_part1("Time:      7  15   30\nDistance:  9  4O  200").unwrap_err();
+        assert_eq!(err.day(), Some(6));
+        assert_eq!(err.line(), Some(2));
+        assert_eq!(err.span(), 14..16);
+
+        let err = generator_day06_part1("Time:      7  15   30\nDistance:  9  40").unwrap_err();
+        assert_eq!(err.line(), Some(2));
+        assert_eq!(err.expected(), "as many distances as times (3)");
+
+        let err = generator_day06_part2("Time:      7  15   30").unwrap_err();
+        assert_eq!(err.line(), Some(2));
+        assert_eq!(err.expected(), "a `Distance:` line");
+
+        let err = generator_day06_part2("Times: 7\nDistance: 9").unwrap_err();
+        assert_eq!(err.line(), Some(1));
+        assert_eq!(err.span(), 0..8);
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let mut rng = Rng::new(6);
+        let number = |rng: &mut Rng| {
+            let digits = rng.vec(1, 30, |rng| char::from(b'0' + rng.below(10) as u8));
+            digits
+                .into_iter()
+                .collect::<String>()
+                .parse::<BigUint>()
+                .unwrap()
+        };
+
+        for _ in 0..100 {
+            let races = rng.vec(1, 5, |rng| Race {
+                time: number(rng),
+                distance: number(rng),
+            });
+
+            assert_eq!(
+                generator_day06_part1(&format_races(&races)),
+                Ok(races.clone())
+            );
+            assert_eq!(
+                generator_day06_part2(&races[0].to_string()),
+                Ok(races[0].clone())
+            );
+        }
 
-        assert_eq!(day_06_part_2(&gen), 71503);
+        let input = "Time:      7  15   30\nDistance:  9  40  200";
+        assert_eq!(format_races(&generator_day06_part1(input).unwrap()), input);
     }
 }
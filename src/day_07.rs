@@ -1,7 +1,9 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
 
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use crate::parse_error::{parse_lines, ParseError};
+
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Card {
     Two,
@@ -20,7 +22,7 @@ pub enum Card {
 }
 
 impl FromStr for Card {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         use Card::*;
@@ -39,11 +41,34 @@ impl FromStr for Card {
             "4" => Ok(Four),
             "3" => Ok(Three),
             "2" => Ok(Two),
-            _ => Err(()),
+            _ => Err(ParseError::at(s, s, "a card (2-9, T, J, Q, K or A)")),
         }
     }
 }
 
+impl Display for Card {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Card::*;
+
+        let label = match self {
+            A => 'A',
+            K => 'K',
+            Q => 'Q',
+            J => 'J',
+            T => 'T',
+            Nine => '9',
+            Eight => '8',
+            Seven => '7',
+            Six => '6',
+            Five => '5',
+            Four => '4',
+            Three => '3',
+            Two => '2',
+        };
+        write!(f, "{label}")
+    }
+}
+
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum HandType {
     HighCard,
@@ -55,91 +80,110 @@ pub enum HandType {
     FiveOfAKind,
 }
 
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub enum RuleSet {
+    #[default]
+    Standard,
+    Jokers,
+}
+
+impl RuleSet {
+    /// Strength of a card for tie-breaking. Under joker rules `J` is the weakest card.
+    pub fn card_strength(&self, card: Card) -> u8 {
+        match (self, card) {
+            (RuleSet::Jokers, Card::J) => 0,
+            (_, card) => card as u8 + 1,
+        }
+    }
+
+    fn is_wildcard(&self, card: Card) -> bool {
+        matches!((self, card), (RuleSet::Jokers, Card::J))
+    }
+}
+
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub struct Hand(Card, Card, Card, Card, Card);
 
 impl PartialOrd<Hand> for Hand {
-    fn partial_cmp(&self, other: &Hand) -> Option<std::cmp::Ordering> {
-        match self.hand_type().partial_cmp(&other.hand_type()) {
-            Some(core::cmp::Ordering::Equal) => {}
-            ord => return ord,
-        }
-        match self.0.partial_cmp(&other.0) {
-            Some(core::cmp::Ordering::Equal) => {}
-            ord => return ord,
-        }
-        match self.1.partial_cmp(&other.1) {
-            Some(core::cmp::Ordering::Equal) => {}
-            ord => return ord,
-        }
-        match self.2.partial_cmp(&other.2) {
-            Some(core::cmp::Ordering::Equal) => {}
-            ord => return ord,
-        }
-        match self.3.partial_cmp(&other.3) {
-            Some(core::cmp::Ordering::Equal) => {}
-            ord => return ord,
-        }
-        self.4.partial_cmp(&other.4)
+    fn partial_cmp(&self, other: &Hand) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
 impl Ord for Hand {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.partial_cmp(other).unwrap()
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cmp_with(other, RuleSet::Standard)
     }
 }
 
 impl FromStr for Hand {
-    type Err = ();
+    type Err = ParseError;
 
-    fn from_str(mut s: &str) -> Result<Self, Self::Err> {
-        s = s.trim();
-        if s.len() != 5 {
-            return Err(());
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        if trimmed.chars().count() != 5 {
+            return Err(ParseError::at(s, trimmed, "a hand of exactly 5 cards"));
         }
 
-        let cards = s
-            .chars()
-            .filter_map(|c| c.to_string().parse::<Card>().ok())
-            .collect::<Vec<_>>();
-
-        if cards.len() != 5 {
-            return Err(());
-        }
+        let cards = trimmed
+            .char_indices()
+            .map(|(i, c)| {
+                let card = &trimmed[i..i + c.len_utf8()];
+                card.parse::<Card>().map_err(|err| err.within(s, card))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
         Ok(Hand(cards[0], cards[1], cards[2], cards[3], cards[4]))
     }
 }
 
+impl Display for Hand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.cards().iter().try_for_each(|card| write!(f, "{card}"))
+    }
+}
+
 impl Hand {
-    pub fn hand_type(&self) -> HandType {
-        let cards = [self.0, self.1, self.2, self.3, self.4];
+    pub fn cards(&self) -> [Card; 5] {
+        [self.0, self.1, self.2, self.3, self.4]
+    }
 
-        let mut map = HashMap::new();
+    pub fn hand_type_with(&self, rules: RuleSet) -> HandType {
+        let mut counts = [0u8; 13];
+        let mut wildcards = 0;
 
-        for card in &cards {
-            let val = map.get(card).unwrap_or(&0);
-            map.insert(card, *val + 1);
+        for card in self.cards() {
+            if rules.is_wildcard(card) {
+                wildcards += 1;
+            } else {
+                counts[card as usize] += 1;
+            }
         }
 
-        match map.keys().len() {
-            1 => HandType::FiveOfAKind,
-            3 => match map.values().max() {
-                Some(3) => HandType::ThreeOfAKind,
-                Some(2) => HandType::TwoPair,
-                _ => unreachable!(),
-            },
-            4 => HandType::OnePair,
-            5 => HandType::HighCard,
-            2 => match map.values().max() {
-                Some(4) => HandType::FourOfAKind,
-                Some(3) => HandType::FullHouse,
-                x => unreachable!("{x:?}"),
-            },
-            _ => unreachable!(),
+        counts.sort_unstable_by(|a, b| b.cmp(a));
+        // wildcards always join the largest group, which is never worse than splitting them up
+        counts[0] += wildcards;
+
+        match (counts[0], counts[1]) {
+            (5, _) => HandType::FiveOfAKind,
+            (4, _) => HandType::FourOfAKind,
+            (3, 2) => HandType::FullHouse,
+            (3, _) => HandType::ThreeOfAKind,
+            (2, 2) => HandType::TwoPair,
+            (2, _) => HandType::OnePair,
+            _ => HandType::HighCard,
         }
     }
+
+    pub fn cmp_with(&self, other: &Hand, rules: RuleSet) -> Ordering {
+        self.hand_type_with(rules)
+            .cmp(&other.hand_type_with(rules))
+            .then_with(|| {
+                self.cards()
+                    .map(|card| rules.card_strength(card))
+                    .cmp(&other.cards().map(|card| rules.card_strength(card)))
+            })
+    }
 }
 
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
@@ -149,47 +193,57 @@ struct HandBid {
 }
 
 impl FromStr for HandBid {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut segments = s.split_whitespace();
-        let Some(hand) = segments.next().and_then(|s| s.parse::<Hand>().ok()) else {
-            return Err(());
+
+        let Some(hand) = segments.next() else {
+            return Err(ParseError::at_end(s, "a hand"));
         };
+        let hand = hand.parse::<Hand>().map_err(|err| err.within(s, hand))?;
 
-        let Some(bid) = segments.next().and_then(|s| s.parse::<u64>().ok()) else {
-            return Err(());
+        let Some(bid) = segments.next() else {
+            return Err(ParseError::at_end(s, "a bid"));
         };
+        let bid = bid
+            .parse::<u64>()
+            .map_err(|_| ParseError::at(s, bid, "a bid"))?;
+
+        if let Some(rest) = segments.next() {
+            return Err(ParseError::at(s, rest, "the end of the line"));
+        }
 
         Ok(HandBid { hand, bid })
     }
 }
 
+impl Display for HandBid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.hand, self.bid)
+    }
+}
+
 impl PartialOrd for HandBid {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.hand.partial_cmp(&other.hand)
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
 impl Ord for HandBid {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+    fn cmp(&self, other: &Self) -> Ordering {
         self.hand.cmp(&other.hand)
     }
 }
 
 #[aoc_generator(day07)]
-fn generator_day07(input: &str) -> Vec<HandBid> {
-    input
-        .lines()
-        .map(|line| line.parse::<HandBid>().expect("invalid input"))
-        .collect()
+fn generator_day07(input: &str) -> Result<Vec<HandBid>, ParseError> {
+    parse_lines(7, input, str::parse)
 }
 
-#[aoc(day07, part1)]
-fn day07_part1(handbids: &[HandBid]) -> u64 {
+fn total_winnings(handbids: &[HandBid], rules: RuleSet) -> u64 {
     let mut handbids = handbids.to_vec();
-    handbids.sort();
-    dbg!(&handbids);
+    handbids.sort_by(|a, b| a.hand.cmp_with(&b.hand, rules));
     handbids
         .iter()
         .enumerate()
@@ -197,9 +251,20 @@ fn day07_part1(handbids: &[HandBid]) -> u64 {
         .sum()
 }
 
+#[aoc(day07, part1)]
+fn day07_part1(handbids: &[HandBid]) -> u64 {
+    total_winnings(handbids, RuleSet::Standard)
+}
+
+#[aoc(day07, part2)]
+fn day07_part2(handbids: &[HandBid]) -> u64 {
+    total_winnings(handbids, RuleSet::Jokers)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::test_rng::Rng;
 
     const INPUT: &str = "32T3K 765
 T55J5 684
@@ -210,23 +275,23 @@ QQQJA 483";
     #[test]
     fn test_hand_type() {
         assert_eq!(
-            Hand(Card::A, Card::A, Card::A, Card::A, Card::A).hand_type(),
+            Hand(Card::A, Card::A, Card::A, Card::A, Card::A).hand_type_with(RuleSet::Standard),
             HandType::FiveOfAKind
         );
         assert_eq!(
-            Hand(Card::A, Card::A, Card::A, Card::A, Card::Q).hand_type(),
+            Hand(Card::A, Card::A, Card::A, Card::A, Card::Q).hand_type_with(RuleSet::Standard),
             HandType::FourOfAKind
         );
         assert_eq!(
-            Hand(Card::A, Card::A, Card::A, Card::Q, Card::Q).hand_type(),
+            Hand(Card::A, Card::A, Card::A, Card::Q, Card::Q).hand_type_with(RuleSet::Standard),
             HandType::FullHouse
         );
         assert_eq!(
-            Hand(Card::A, Card::Q, Card::A, Card::Q, Card::Q).hand_type(),
+            Hand(Card::A, Card::Q, Card::A, Card::Q, Card::Q).hand_type_with(RuleSet::Standard),
             HandType::FullHouse
         );
         assert_eq!(
-            Hand(Card::Two, Card::Q, Card::A, Card::Two, Card::Q).hand_type(),
+            Hand(Card::Two, Card::Q, Card::A, Card::Two, Card::Q).hand_type_with(RuleSet::Standard),
             HandType::TwoPair
         );
     }
@@ -245,7 +310,7 @@ QQQJA 483";
     fn test_generator_day07() {
         use Card::*;
         assert_eq!(
-            generator_day07(INPUT),
+            generator_day07(INPUT).unwrap(),
             vec![
                 HandBid {
                     hand: Hand(Three, Two, T, Three, K),
@@ -273,7 +338,126 @@ QQQJA 483";
 
     #[test]
     fn test_day07_part1() {
-        let gen = generator_day07(INPUT);
+        let gen = generator_day07(INPUT).unwrap();
         assert_eq!(day07_part1(&gen), 6440);
     }
+
+    #[test]
+    fn test_hand_type_jokers() {
+        use Card::*;
+
+        let jokers = RuleSet::Jokers;
+        assert_eq!(
+            Hand(J, J, J, J, J).hand_type_with(jokers),
+            HandType::FiveOfAKind
+        );
+        assert_eq!(
+            Hand(J, J, J, J, A).hand_type_with(jokers),
+            HandType::FiveOfAKind
+        );
+        assert_eq!(
+            Hand(Q, J, J, Q, Two).hand_type_with(jokers),
+            HandType::FourOfAKind
+        );
+        assert_eq!(
+            Hand(K, T, J, J, T).hand_type_with(jokers),
+            HandType::FourOfAKind
+        );
+        assert_eq!(
+            Hand(Two, Two, J, Three, Three).hand_type_with(jokers),
+            HandType::FullHouse
+        );
+        assert_eq!(
+            Hand(Two, J, Four, Three, Five).hand_type_with(jokers),
+            HandType::OnePair
+        );
+        assert_eq!(
+            Hand(Three, Two, T, Three, K).hand_type_with(jokers),
+            HandType::OnePair
+        );
+
+        assert_eq!(
+            Hand(J, J, J, J, J).hand_type_with(RuleSet::Standard),
+            HandType::FiveOfAKind
+        );
+        assert_eq!(
+            Hand(K, T, J, J, T).hand_type_with(RuleSet::Standard),
+            HandType::TwoPair
+        );
+    }
+
+    #[test]
+    fn test_hand_order_jokers() {
+        use Card::*;
+
+        let jokers = RuleSet::Jokers;
+        assert_eq!(
+            Hand(J, K, K, K, Two).cmp_with(&Hand(Q, Q, Q, Q, Two), jokers),
+            Ordering::Less
+        );
+        assert_eq!(
+            Hand(J, J, J, J, J).cmp_with(&Hand(Two, Two, Two, Two, Two), jokers),
+            Ordering::Less
+        );
+        assert_eq!(
+            Hand(J, J, J, J, J).cmp_with(&Hand(Two, Two, Two, Two, Two), RuleSet::Standard),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn test_day07_part2() {
+        let gen = generator_day07(INPUT).unwrap();
+        assert_eq!(day07_part2(&gen), 5905);
+    }
+
+    #[test]
+    fn test_generator_day07_errors() {
+        let err = generator_day07("32T3K 765\nT55X5 684").unwrap_err();
+        assert_eq!(err.day(), Some(7));
+        assert_eq!(err.line(), Some(2));
+        assert_eq!(err.span(), 3..4);
+        assert_eq!(err.expected(), "a card (2-9, T, J, Q, K or A)");
+
+        let err = generator_day07("32T3 765").unwrap_err();
+        assert_eq!(err.span(), 0..4);
+        assert_eq!(err.expected(), "a hand of exactly 5 cards");
+
+        let err = generator_day07("32T3K").unwrap_err();
+        assert_eq!(err.span(), 5..5);
+        assert_eq!(err.expected(), "a bid");
+
+        let err = generator_day07("32T3K -1").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day 7, line 1, column 7: expected a bid
+  |
+1 | 32T3K -1
+  |       ^^"
+        );
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        use Card::*;
+
+        let mut rng = Rng::new(7);
+        let cards = [
+            Two, Three, Four, Five, Six, Seven, Eight, Nine, T, J, Q, K, A,
+        ];
+
+        for _ in 0..200 {
+            let mut card = || *rng.pick(&cards);
+            let handbid = HandBid {
+                hand: Hand(card(), card(), card(), card(), card()),
+                bid: rng.next_u64(),
+            };
+
+            assert_eq!(HandBid::from_str(&handbid.to_string()), Ok(handbid));
+        }
+
+        for line in INPUT.lines() {
+            assert_eq!(HandBid::from_str(line).unwrap().to_string(), line);
+        }
+    }
 }
@@ -0,0 +1,245 @@
+use std::str::FromStr;
+
+use crate::parse_error::ParseError;
+
+const OFFSETS_4: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+
+const OFFSETS_8: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// A rectangular grid stored row by row. Coordinates are `(x, y)` with `x` being the
+/// column and `y` the row, both starting at the top left corner.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Builds a grid from its rows, which all need to have the same length.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Option<Self> {
+        let width = rows.first().map(Vec::len).unwrap_or(0);
+        if rows.iter().any(|row| row.len() != width) {
+            return None;
+        }
+
+        Some(Grid {
+            width,
+            height: rows.len(),
+            cells: rows.into_iter().flatten().collect(),
+        })
+    }
+
+    /// Parses one cell per character, one row per line.
+    pub fn parse_with(input: &str, parse: impl Fn(char) -> Option<T>) -> Result<Self, ParseError> {
+        let mut width = None;
+        let mut height = 0;
+        let mut cells = vec![];
+
+        for (y, line) in input.lines().enumerate() {
+            let mut row_width = 0;
+            for (i, c) in line.char_indices() {
+                let cell = parse(c).ok_or_else(|| {
+                    ParseError::at(line, &line[i..i + c.len_utf8()], "a valid grid cell")
+                        .on_line(y + 1, line)
+                })?;
+                cells.push(cell);
+                row_width += 1;
+            }
+
+            match width {
+                None => width = Some(row_width),
+                Some(width) if width != row_width => {
+                    return Err(
+                        ParseError::at(line, line, format!("a row of {width} cells"))
+                            .on_line(y + 1, line),
+                    )
+                }
+                Some(_) => {}
+            }
+            height += 1;
+        }
+
+        Ok(Grid {
+            width: width.unwrap_or(0),
+            height,
+            cells,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        self.contains(x, y).then(|| &self.cells[y * self.width + x])
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        if !self.contains(x, y) {
+            return None;
+        }
+        Some(&mut self.cells[y * self.width + x])
+    }
+
+    fn offset(&self, x: usize, y: usize, (dx, dy): (isize, isize)) -> Option<(usize, usize)> {
+        let x = x.checked_add_signed(dx)?;
+        let y = y.checked_add_signed(dy)?;
+        self.contains(x, y).then_some((x, y))
+    }
+
+    /// The orthogonal neighbours of `(x, y)` that lie inside the grid.
+    pub fn neighbours4(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize, &T)> {
+        OFFSETS_4
+            .into_iter()
+            .filter_map(move |offset| self.offset(x, y, offset))
+            .map(|(x, y)| (x, y, &self.cells[y * self.width + x]))
+    }
+
+    /// The orthogonal and diagonal neighbours of `(x, y)` that lie inside the grid,
+    /// in reading order.
+    pub fn neighbours8(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize, &T)> {
+        OFFSETS_8
+            .into_iter()
+            .filter_map(move |offset| self.offset(x, y, offset))
+            .map(|(x, y)| (x, y, &self.cells[y * self.width + x]))
+    }
+
+    pub fn row(&self, y: usize) -> Option<&[T]> {
+        (y < self.height).then(|| &self.cells[y * self.width..(y + 1) * self.width])
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.height).map(|y| &self.cells[y * self.width..(y + 1) * self.width])
+    }
+
+    pub fn column(&self, x: usize) -> Option<impl Iterator<Item = &T>> {
+        (x < self.width).then(|| self.cells.iter().skip(x).step_by(self.width))
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(|x| self.cells.iter().skip(x).step_by(self.width))
+    }
+
+    /// Every cell with its coordinates, in reading order.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, &T)> {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| (i % self.width, i / self.width, cell))
+    }
+}
+
+impl FromStr for Grid<char> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Grid::parse_with(s, Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "abc
+def
+ghi";
+
+    #[test]
+    fn test_parse() {
+        let grid = INPUT.parse::<Grid<char>>().unwrap();
+
+        assert_eq!(grid.width(), 3);
+        assert_eq!(grid.height(), 3);
+        assert_eq!(grid.get(1, 2), Some(&'h'));
+        assert_eq!(grid.get(3, 0), None);
+        assert_eq!(grid.get(0, 3), None);
+
+        let digits = Grid::parse_with("12\n34", |c| c.to_digit(10)).unwrap();
+        assert_eq!(digits.get(1, 1), Some(&4));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = "abc\nde\nfgh".parse::<Grid<char>>().unwrap_err();
+        assert_eq!(err.line(), Some(2));
+        assert_eq!(err.expected(), "a row of 3 cells");
+
+        let err = Grid::parse_with("12\n3x", |c| c.to_digit(10)).unwrap_err();
+        assert_eq!(err.line(), Some(2));
+        assert_eq!(err.span(), 1..2);
+    }
+
+    #[test]
+    fn test_from_rows() {
+        assert_eq!(
+            Grid::from_rows(vec![vec![1, 2], vec![3, 4]]),
+            Some(Grid {
+                width: 2,
+                height: 2,
+                cells: vec![1, 2, 3, 4]
+            })
+        );
+        assert_eq!(Grid::from_rows(vec![vec![1, 2], vec![3]]), None);
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = INPUT.parse::<Grid<char>>().unwrap();
+
+        let cells = |iter: Box<dyn Iterator<Item = (usize, usize, &char)> + '_>| {
+            iter.map(|(_, _, c)| *c).collect::<String>()
+        };
+
+        assert_eq!(cells(Box::new(grid.neighbours4(1, 1))), "bdfh");
+        assert_eq!(cells(Box::new(grid.neighbours8(1, 1))), "abcdfghi");
+        assert_eq!(cells(Box::new(grid.neighbours4(0, 0))), "bd");
+        assert_eq!(cells(Box::new(grid.neighbours8(2, 2))), "efh");
+        assert_eq!(
+            grid.neighbours8(0, 2).collect::<Vec<_>>(),
+            vec![(0, 1, &'d'), (1, 1, &'e'), (1, 2, &'h')]
+        );
+    }
+
+    #[test]
+    fn test_rows_and_columns() {
+        let mut grid = INPUT.parse::<Grid<char>>().unwrap();
+
+        assert_eq!(
+            grid.rows()
+                .map(|row| row.iter().collect())
+                .collect::<Vec<String>>(),
+            vec!["abc", "def", "ghi"]
+        );
+        assert_eq!(
+            grid.columns()
+                .map(|column| column.collect())
+                .collect::<Vec<String>>(),
+            vec!["adg", "beh", "cfi"]
+        );
+        assert_eq!(grid.row(1), Some(&['d', 'e', 'f'][..]));
+        assert!(grid.column(3).is_none());
+
+        *grid.get_mut(2, 1).unwrap() = 'x';
+        assert_eq!(grid.column(2).unwrap().collect::<String>(), "cxi");
+        assert_eq!(grid.iter().nth(5), Some((2, 1, &'x')));
+    }
+}
@@ -0,0 +1,63 @@
+//! Just enough JSON writing for the report exports, so that they share one set of
+//! escaping rules without pulling in a dependency.
+
+/// `s` as a quoted JSON string. Quotes, backslashes and control characters are escaped,
+/// everything else is written as is.
+pub fn string(s: &str) -> String {
+    let mut json = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// An array of values that are already JSON.
+pub fn array(values: impl IntoIterator<Item = String>) -> String {
+    format!("[{}]", values.into_iter().collect::<Vec<_>>().join(","))
+}
+
+/// An object with the given keys, in order, and values that are already JSON.
+pub fn object<'a>(fields: impl IntoIterator<Item = (&'a str, String)>) -> String {
+    let fields = fields
+        .into_iter()
+        .map(|(key, value)| format!("{}:{value}", string(key)))
+        .collect::<Vec<_>>();
+    format!("{{{}}}", fields.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string() {
+        assert_eq!(string("two"), r#""two""#);
+        assert_eq!(string(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(string(r"a\b"), r#""a\\b""#);
+        assert_eq!(string("a\nb\r\tc"), r#""a\nb\r\tc""#);
+        assert_eq!(string("\u{0}\u{1f}\u{7f}"), r#""\u0000\u001f\u007f""#);
+        assert_eq!(string("fünf €"), "\"fünf €\"");
+        assert_eq!(string(""), r#""""#);
+    }
+
+    #[test]
+    fn test_array_and_object() {
+        assert_eq!(array([]), "[]");
+        assert_eq!(array(["1".to_string(), string("x")]), r#"[1,"x"]"#);
+
+        assert_eq!(object([]), "{}");
+        assert_eq!(
+            object([("id", "1".to_string()), ("a\"b", array([]))]),
+            r#"{"id":1,"a\"b":[]}"#
+        );
+    }
+}
@@ -1,3 +1,6 @@
+mod aho_corasick;
+pub mod bag_inference;
+mod big_uint;
 mod day_01;
 mod day_02;
 mod day_03;
@@ -5,6 +8,30 @@ mod day_04;
 mod day_05;
 mod day_06;
 mod day_07;
+mod grid;
+mod json;
+mod parse_error;
+#[cfg(test)]
+mod test_rng;
+
+pub use day_01::{
+    calibration_value, explain, explain_json, generator_day01_part2, numerals, sum_calibration,
+    sum_calibration_digits, CalibrationNumber, CalibrationReader, EnglishNumerals, Language,
+    Lexicon, LexiconError, Numeral, StreamError, Token, Tokenizer, Trace,
+};
+pub use day_02::{possible_games, sum_of_powers, Bag, Color, Game, Information, Round};
+pub use day_03::{Number, Schematic, Symbol};
+pub use day_04::{
+    copy_report, copy_report_csv, copy_report_json, count_copies, total_cards, total_score, Capped,
+    Card, CardReport, CardTable, CopyError, CopyMode, Doubling, Fibonacci, Linear, ScoreError,
+    ScoringFn, ScoringRule, TableError,
+};
+pub use day_05::{
+    ConversionMap, ConversionRange, Ingredients, Input as Almanac, OverlapError, PiecewiseMap,
+    Route, RouteError, SeedError,
+};
+pub use grid::Grid;
+pub use parse_error::ParseError;
 
 use aoc_runner_derive::aoc_lib;
 
@@ -0,0 +1,222 @@
+use std::{error::Error, fmt::Display, ops::Range};
+
+/// A parse failure that points at the offending part of the puzzle input.
+///
+/// Errors start out relative to whatever fragment was being parsed and are moved
+/// outwards with [`ParseError::within`] until they are pinned to a line with
+/// [`ParseError::on_line`] and to a day with [`ParseError::in_day`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    expected: String,
+    span: Range<usize>,
+    day: Option<u8>,
+    line: Option<(usize, String)>,
+}
+
+/// Byte offset of `fragment` inside `input`, `None` if `fragment` is not a subslice of it.
+fn offset_in(input: &str, fragment: &str) -> Option<usize> {
+    let offset = (fragment.as_ptr() as usize).checked_sub(input.as_ptr() as usize)?;
+    (offset + fragment.len() <= input.len()).then_some(offset)
+}
+
+impl ParseError {
+    pub fn new(span: Range<usize>, expected: impl Into<String>) -> Self {
+        ParseError {
+            expected: expected.into(),
+            span,
+            day: None,
+            line: None,
+        }
+    }
+
+    /// An error covering `fragment`, which should be a subslice of `input`. Any other
+    /// fragment is reported at the end of `input`.
+    pub fn at(input: &str, fragment: &str, expected: impl Into<String>) -> Self {
+        match offset_in(input, fragment) {
+            Some(start) => ParseError::new(start..start + fragment.len(), expected),
+            None => ParseError::at_end(input, expected),
+        }
+    }
+
+    /// An empty error right behind the end of `input`, for things that are missing.
+    pub fn at_end(input: &str, expected: impl Into<String>) -> Self {
+        ParseError::new(input.len()..input.len(), expected)
+    }
+
+    /// Moves an error raised while parsing `fragment` so that it points into `input`.
+    /// If `fragment` is not a subslice of `input`, the error moves to the end of `input`.
+    pub fn within(mut self, input: &str, fragment: &str) -> Self {
+        self.span = match offset_in(input, fragment) {
+            Some(offset) => self.span.start + offset..self.span.end + offset,
+            None => input.len()..input.len(),
+        };
+        self
+    }
+
+    /// Pins the error to the (1-based) line `line` whose content is `text`.
+    pub fn on_line(mut self, line: usize, text: &str) -> Self {
+        self.line = Some((line, text.to_string()));
+        self
+    }
+
+    pub fn in_day(mut self, day: u8) -> Self {
+        self.day = Some(day);
+        self
+    }
+
+    pub fn expected(&self) -> &str {
+        &self.expected
+    }
+
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    pub fn day(&self) -> Option<u8> {
+        self.day
+    }
+
+    pub fn line(&self) -> Option<usize> {
+        self.line.as_ref().map(|(line, _)| *line)
+    }
+}
+
+/// Parses every line of `input` with `parse`, pinning errors to their line and day.
+pub fn parse_lines<T>(
+    day: u8,
+    input: &str,
+    parse: impl Fn(&str) -> Result<T, ParseError>,
+) -> Result<Vec<T>, ParseError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| parse(line).map_err(|err| err.on_line(i + 1, line).in_day(day)))
+        .collect()
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(day) = self.day {
+            write!(f, "day {day}, ")?;
+        }
+
+        let Some((line, text)) = &self.line else {
+            return write!(
+                f,
+                "column {}: expected {}",
+                self.span.start + 1,
+                self.expected
+            );
+        };
+
+        // columns count characters, not bytes
+        let column = text
+            .get(..self.span.start)
+            .map(|prefix| prefix.chars().count())
+            .unwrap_or(self.span.start);
+        let width = text
+            .get(self.span.clone())
+            .map(|fragment| fragment.chars().count())
+            .unwrap_or(0)
+            .max(1);
+
+        writeln!(
+            f,
+            "line {line}, column {}: expected {}",
+            column + 1,
+            self.expected
+        )?;
+
+        let gutter = " ".repeat(line.to_string().len());
+        writeln!(f, "{gutter} |")?;
+        writeln!(f, "{line} | {text}")?;
+        write!(f, "{gutter} | {}{}", " ".repeat(column), "^".repeat(width))
+    }
+}
+
+impl Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_within() {
+        let line = "Card 1: 41 x8 | 83";
+        let fragment = &line[11..13];
+
+        let err = ParseError::new(0..2, "a number").within(line, fragment);
+        assert_eq!(err.span(), 11..13);
+        assert_eq!(err, ParseError::at(line, fragment, "a number"));
+    }
+
+    #[test]
+    fn test_foreign_fragment() {
+        let line = "Card 1: 41 x8 | 83";
+        let copy = line[11..13].to_string();
+        let numbers = &line[8..];
+
+        // fragments that are not part of the input end up at its end instead of panicking
+        assert_eq!(ParseError::at(line, &copy, "a number").span(), 18..18);
+        assert_eq!(
+            ParseError::at(numbers, &line[..4], "a number").span(),
+            10..10
+        );
+        assert_eq!(ParseError::at(&line[..4], numbers, "a number").span(), 4..4);
+        assert_eq!(
+            ParseError::new(0..2, "a number").within(line, &copy).span(),
+            18..18
+        );
+
+        let err = ParseError::at(numbers, &line[..4], "a number").on_line(1, numbers);
+        assert!(err.to_string().contains("expected a number"));
+    }
+
+    #[test]
+    fn test_render() {
+        let line = "Card 1: 41 x8 | 83";
+        let err = ParseError::at(line, &line[11..13], "a number")
+            .on_line(12, line)
+            .in_day(4);
+
+        assert_eq!(
+            err.to_string(),
+            "day 4, line 12, column 12: expected a number
+   |
+12 | Card 1: 41 x8 | 83
+   |            ^^"
+        );
+    }
+
+    #[test]
+    fn test_render_missing() {
+        let line = "Game 1";
+        let err = ParseError::at_end(line, "`:`").on_line(1, line);
+
+        assert_eq!(
+            err.to_string(),
+            "line 1, column 7: expected `:`
+  |
+1 | Game 1
+  |       ^"
+        );
+        assert_eq!(
+            ParseError::new(3..4, "a digit").to_string(),
+            "column 4: expected a digit"
+        );
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        let input = "1\n2\nthree\n4";
+        let err = parse_lines(1, input, |line| {
+            line.parse::<u32>()
+                .map_err(|_| ParseError::at(line, line, "a number"))
+        })
+        .unwrap_err();
+
+        assert_eq!(err.day(), Some(1));
+        assert_eq!(err.line(), Some(3));
+        assert_eq!(err.span(), 0..5);
+    }
+}
@@ -0,0 +1,38 @@
+/// A small xorshift generator for property tests, so that failures are reproducible
+/// without pulling in a dependency.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // xorshift gets stuck on 0
+        Rng(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A number in `0..bound`. `bound` must not be 0.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len() as u64) as usize]
+    }
+
+    /// Between `min` and `max` values from `generate`.
+    pub fn vec<T>(
+        &mut self,
+        min: usize,
+        max: usize,
+        mut generate: impl FnMut(&mut Self) -> T,
+    ) -> Vec<T> {
+        let len = min + self.below((max - min + 1) as u64) as usize;
+        (0..len).map(|_| generate(self)).collect()
+    }
+}